@@ -44,6 +44,7 @@ mod test {
     use super::*;
 
     #[test]
+    #[allow(clippy::explicit_counter_loop)]
     fn calc_score_uses_fibonacci() {
         let guesses_to_score_map: [u32; 21] = [
             0, 1, 2, 3, 4, 4, 5, 5, 5, 6, 6, 6, 6, 6, 7, 7, 7, 7, 7, 7, 7,
@@ -6,20 +6,28 @@ mod guessing_game;
 mod math;
 mod my_macros;
 mod story;
+#[cfg(test)]
+mod test_support;
 mod vehicles;
 
 use byteorder::{BigEndian, ReadBytesExt};
 use greetings::english::greet;
 use hashbrown::HashSet;
+use indicatif::{ProgressBar, ProgressStyle};
 use math::*;
+use std::fs::File;
 use std::io;
-use std::{thread, time};
-use story::read_story;
-use tokio::sync::oneshot;
+use std::path::Path;
+use std::thread;
+use story::{
+    read_stories, read_story, FetchClient, FetchOptions, Progress, ProgressSender, StoryReport,
+    StorySource,
+};
 use vehicles::drive::test_drive;
 
 const DATA_SOURCE_URL: &str =
     "https://raw.githubusercontent.com/BernardIgiri/learn_more_rust/master/data/book.txt";
+const BOOK_PATH: &str = "data/book.txt";
 
 fn main() {
     {
@@ -82,6 +90,47 @@ fn main() {
             inverse,
         );
     }
+    {
+        let big = math::fib_big(1000);
+        println!(
+            "1000 has a fib of {} and an inverse fib of {}.",
+            big,
+            math::fib_inverse_big(&big).unwrap_or(0),
+        );
+    }
+    println!(
+        "A trillion has a fib mod 1000 of {} which repeats every {}.",
+        math::fib_mod(1_000_000_000_000, 1000).unwrap_or(0),
+        math::pisano_period(1000).unwrap_or(0),
+    );
+    {
+        let values = [100, 2023, u64::MAX];
+        let encoded = math::fib_encode_bytes(&values).unwrap_or_default();
+        println!(
+            "100 is the sum of fibs {:?}, {:?} fits in {} bytes and decodes to {:?}.",
+            math::zeckendorf(100),
+            values,
+            encoded.len(),
+            math::fib_decode_bytes(&encoded),
+        );
+    }
+    for (name, sequence) in [
+        ("Fibonacci", LinearRecurrence::fibonacci()),
+        ("Lucas", LinearRecurrence::lucas()),
+        ("Pell", LinearRecurrence::pell()),
+        ("Tribonacci", LinearRecurrence::tribonacci()),
+    ] {
+        println!(
+            "{} numbers {:?}, 29 is at {:?} and 30 rounds up to {:?}.",
+            name,
+            sequence.clone().take(10).collect::<Vec<u64>>(),
+            sequence.inverse(29),
+            sequence.inverse_rounded_up(30),
+        );
+    }
+    if let Some(ones) = LinearRecurrence::new(vec![1], vec![1]) {
+        println!("A sequence of ones never reaches 2, {:?}.", ones.inverse(2));
+    }
     println!("Let's go!");
     let score = guessing_game::guessing_game(1, 50);
     let mut car = get_prize(score);
@@ -96,16 +145,204 @@ fn main() {
     let story = prompt("What will you do with your winnings?");
     let words = word_freq(&story);
     let letters = letter_freq(&story);
-    let lorem_ipsum_japanese = letter_freq("旅ロ京青利セムレ弱改フヨス波府かばぼ意送でぼ調掲察たス日西重ケアナ住橋ユムミク順待ふかんぼ人奨貯鏡すびそ。");
-    let lorem_ipsum_russian = letter_freq("Лорем ипсум долор сит амет, пер цлита поссит ех, ат мунере фабулас петентиум сит. Иус цу цибо саперет сцрипсерит,");
+    let japanese = "旅ロ京青利セムレ弱改フヨス波府かばぼ意送でぼ調掲察たス日西重ケアナ住橋ユムミク順待ふかんぼ人奨貯鏡すびそ。";
+    let russian = "Лорем ипсум долор сит амет, пер цлита поссит ех, ат мунере фабулас петентиум сит. Иус цу цибо саперет сцрипсерит,";
+    let lorem_ipsum_japanese = letter_freq(japanese);
+    let lorem_ipsum_russian = letter_freq(russian);
     println!("Word frequency\n{:#?}", words);
     println!("Letter frequency\n{:?}", letters);
     println!("Lorem Ipsum Japanese\n{:?}", lorem_ipsum_japanese);
     println!("Lorem Ipsum Russian\n{:?}", lorem_ipsum_russian);
+    for (name, text) in [("Japanese", japanese), ("Russian", russian)] {
+        let freq = classified_letter_freq(text);
+        let scripts = freq
+            .script_totals()
+            .into_iter()
+            .map(|(script, count)| format!("{} {}", script.full_name(), count))
+            .collect::<Vec<String>>();
+        let punctuation = freq
+            .by_category()
+            .get(&CharCategory::Punctuation)
+            .map_or(0, |marks| marks.values().sum::<u32>());
+        println!(
+            "{} has {} distinct letters and {} punctuation marks across {}",
+            name,
+            freq.letters().len(),
+            punctuation,
+            scripts.join(", ")
+        );
+    }
+    let detector = LanguageDetector::new();
+    for (name, text) in [
+        ("Your story", story.as_str()),
+        ("Japanese", japanese),
+        ("Russian", russian),
+    ] {
+        match detector.detect(text).first() {
+            Some(guess) => println!(
+                "{} looks like {} ({:.0}% confident)",
+                name,
+                guess.language,
+                guess.confidence * 100.0
+            ),
+            None => println!("{} is in no language I know", name),
+        }
+    }
+    for options in [
+        NormalizationOptions::default(),
+        NormalizationOptions {
+            case: CaseMode::Unchanged,
+            ..Default::default()
+        },
+        NormalizationOptions {
+            form: NormalizationForm::Nfc,
+            ..Default::default()
+        },
+        NormalizationOptions {
+            form: NormalizationForm::Nfkc,
+            case: CaseMode::Fold,
+            strip_accents: true,
+            stopwords: english_stopwords(),
+        },
+    ] {
+        let text = "The Café served the cafe\u{301} ﬁne Straße coffee";
+        println!(
+            "Normalized with {:?} {:?}\n{:?}\n{:?}",
+            options.form,
+            options.case,
+            word_freq_with(text, &options),
+            letter_freq_with(text, &options)
+        );
+    }
+    match stream_book() {
+        Ok(book) => {
+            let mut counter = FrequencyCounter::new();
+            counter.push_str(&story);
+            counter.merge(book);
+            let (words, letters) = counter.finish();
+            println!(
+                "Your story and the book use {} distinct words and {} distinct letters.",
+                words.len(),
+                letters.len()
+            );
+        }
+        Err(e) => println!("Could not read {}: {}", BOOK_PATH, e),
+    }
+    match merge_into_file(
+        word_freq(&story),
+        std::env::temp_dir().join("learn_more_rust_stories.tsv"),
+    ) {
+        Ok(all) => println!("All your stories so far use {} distinct words.", all.len()),
+        Err(e) => println!("Could not save your story: {}", e),
+    }
+    if let Ok(book) = std::fs::read_to_string(BOOK_PATH) {
+        let snapshot = std::env::temp_dir().join("learn_more_rust_book.freq");
+        let book_words = word_freq(book.as_str());
+        match save_freq(&book_words, &snapshot).and_then(|_| load_freq(&snapshot)) {
+            Ok(mut saved) => {
+                let mut with_story = book_words;
+                merge_freq(&mut with_story, word_freq(&story));
+                let diff = diff_freq(&saved, &with_story);
+                apply_freq_diff(&mut saved, &diff);
+                println!(
+                    "Adding your story to the saved book changes {} counts, {} words in total.",
+                    diff.len(),
+                    saved.values().sum::<u32>()
+                );
+            }
+            Err(e) => println!("Could not save the book's word counts: {}", e),
+        }
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        println!(
+            "Counted {} distinct words and {} distinct letters in the book on {} threads.",
+            par_word_freq(&book, threads).len(),
+            par_letter_freq(&book, threads).len(),
+            threads
+        );
+        println!(
+            "The book has {} distinct word pairs and {} distinct letter trigrams.",
+            word_ngram_freq(book.as_str(), 2).len(),
+            letter_ngram_freq(book.as_str(), 3).len()
+        );
+        let document = story::gutenberg::parse(&book);
+        if let Some(chapter) = document
+            .chapters
+            .iter()
+            .max_by_key(|chapter| chapter.word_freq().values().sum::<u32>())
+        {
+            println!(
+                "{} by {} ({}) has {} chapters, the longest is {} with {} distinct letters.",
+                document.metadata.title.as_deref().unwrap_or("Untitled"),
+                document.metadata.author.as_deref().unwrap_or("Anonymous"),
+                document
+                    .metadata
+                    .release_date
+                    .as_deref()
+                    .unwrap_or("undated"),
+                document.chapters.len(),
+                chapter.title,
+                chapter.letter_freq().len()
+            );
+        }
+        let concordance = story::Concordance::new(document.body.clone());
+        println!(
+            "The book has {} words and first mentions Pemberley at word {:?}.",
+            concordance.word_count(),
+            concordance.positions("pemberley").first()
+        );
+        for line in concordance.kwic("single man", 30).iter().take(3) {
+            println!("{}", line);
+        }
+        let mut index = story::SearchIndex::new();
+        for chapter in &document.chapters {
+            index.add(chapter.title.as_str(), &chapter.text);
+        }
+        let query = "pemberley AND housekeeper NOT wickham";
+        if let Some(hits) = index.search(query) {
+            let found = hits.iter().take(3).map(|hit| hit.name.as_str());
+            println!(
+                "Searching {} chapters for {} found {:?}",
+                index.len(),
+                query,
+                found.collect::<Vec<&str>>()
+            );
+        }
+        let mut chain = story::MarkovChain::new(2);
+        chain.train(&document.body);
+        println!(
+            "From {} word pairs, a new story: {}",
+            chain.state_count(),
+            chain.generate_seeded(rand::random(), 30)
+        );
+        let mut chapters = Corpus::new();
+        for chapter in &document.chapters {
+            chapters.add(chapter.word_freq());
+        }
+        if !chapters.is_empty() {
+            println!(
+                "Chapter 1 tf-idf: {:?}",
+                chapters.top_tf_idf(0, 5).unwrap_or_default()
+            );
+        }
+        if let Some(found) = chapters.distinctive_words(0) {
+            let words = found.iter().take(5).map(|w| w.word.as_str());
+            println!(
+                "Most distinctive to chapter 1: {:?}",
+                words.collect::<Vec<&str>>()
+            );
+        }
+        compare_chapters(document.chapters.iter().take(8).map(|c| c.text.clone()));
+        for c in collocations(book, 20).iter().take(5) {
+            println!(
+                "\"{} {}\" appears {} times, PMI {:.2}, G² {:.1}",
+                c.first, c.second, c.count, c.pmi, c.log_likelihood
+            );
+        }
+    }
     let my_box = data_types::MyBox::new(5);
     println!("Box: {}", *my_box);
     println!("Story Time!");
-    wait_for_story();
+    wait_for_story(story_source());
     println!(
         "Match test\n{}\n{}\n{}\n{}\n",
         match_stuff(3, "cat", -0.1),
@@ -117,27 +354,94 @@ fn main() {
     play_with_numbers();
 }
 
-#[tokio::main]
-async fn wait_for_story() {
-    let delay_time = time::Duration::from_millis(50);
-    let (tx, mut rx) = oneshot::channel();
-    tokio::spawn(async move {
-        let result = match read_story(DATA_SOURCE_URL.into()).await {
-            Ok(story) => story,
-            Err(e) => e.to_string(),
-        };
-        tx.send(result).unwrap();
+/// Reads the story on another task while drawing its progress until it
+/// finishes or Ctrl-C cancels it. The runtime shuts down in the background so
+/// a cancelled analysis left on a blocking thread is not waited for.
+fn wait_for_story(source: StorySource) {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => return println!("{}", e),
+    };
+    runtime.block_on(show_story(source));
+    runtime.shutdown_background();
+}
+
+async fn show_story(source: StorySource) {
+    let (progress, mut updates) = ProgressSender::channel();
+    let options = FetchOptions {
+        cache_dir: Some(std::env::temp_dir().join("learn_more_rust")),
+        ..Default::default()
+    };
+    let mut story = tokio::spawn(async move {
+        let client = FetchClient::new(options)?;
+        read_story(source, &client, &progress).await
     });
-    while match rx.try_recv() {
-        Err(_) => true,
-        Ok(story) => {
-            println!("{}", story);
-            false
+    let cancel = tokio::signal::ctrl_c();
+    tokio::pin!(cancel);
+    let bar = ProgressBar::new_spinner();
+    loop {
+        tokio::select! {
+            Some(update) = updates.recv() => show_progress(&bar, update),
+            result = &mut story => {
+                bar.finish_and_clear();
+                match result {
+                    Ok(Ok(story)) => println!("{}", render_story(&story)),
+                    Ok(Err(e)) => println!("{}", e),
+                    Err(e) => println!("Story task failed: {}", e),
+                }
+                break;
+            }
+            _ = &mut cancel => {
+                story.abort();
+                bar.abandon_with_message("cancelled");
+                println!("Story cancelled");
+                break;
+            }
+        }
+    }
+}
+
+/// Reads chapters as a batch of stories to compare them with the whole set
+#[tokio::main]
+async fn compare_chapters<I: Iterator<Item = String>>(chapters: I) {
+    let client = match FetchClient::new(FetchOptions::default()) {
+        Ok(client) => client,
+        Err(e) => return println!("{}", e),
+    };
+    let sources = chapters.map(StorySource::Text).collect();
+    let batch = read_stories(sources, &client, 4).await;
+    for (index, report) in batch.reports.iter().enumerate() {
+        match report {
+            Ok(report) => println!(
+                "Chapter {} has {} words, reading ease {:.1}",
+                index + 1,
+                report.word_count,
+                report.readability.flesch_reading_ease
+            ),
+            Err(e) => println!("Chapter {} failed: {}", index + 1, e),
+        }
+    }
+    println!("Across those chapters: {:?}", batch.top_words(5));
+}
+
+fn show_progress(bar: &ProgressBar, update: Progress) {
+    match update {
+        Progress::Downloaded { bytes, total } => {
+            if let Some(total) = total.filter(|total| bar.length() != Some(*total as u64)) {
+                bar.set_length(total as u64);
+                bar.set_style(
+                    ProgressStyle::with_template("{msg} [{bar:40}] {bytes}/{total_bytes}")
+                        .unwrap_or_else(|_| ProgressStyle::default_bar()),
+                );
+            }
+            bar.set_message("downloading");
+            bar.set_position(bytes as u64);
         }
-    } {
-        println!("Waiting for story...");
-        thread::sleep(delay_time);
+        Progress::Decoding => bar.set_message("decoding"),
+        Progress::Parsing => bar.set_message("parsing"),
+        Progress::Analyzing => bar.set_message("analyzing"),
     }
+    bar.tick();
 }
 
 fn match_stuff(n: u32, s: &str, f: f32) -> String {
@@ -180,6 +484,33 @@ fn prompt<S: Into<String> + std::fmt::Display>(text: S) -> String {
     input.trim().to_string()
 }
 
+/// Reads the story from the first argument if given, otherwise from the
+/// bundled book so the demo works offline, falling back to GitHub
+fn story_source() -> StorySource {
+    match std::env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(source) => source.as_str().into(),
+        None if Path::new(BOOK_PATH).exists() => StorySource::File(BOOK_PATH.into()),
+        None => DATA_SOURCE_URL.into(),
+    }
+}
+
+/// Renders the report as JSON or CSV when --json or --csv is passed
+fn render_story(story: &StoryReport) -> String {
+    if std::env::args().any(|arg| arg == "--json") {
+        story.to_json().unwrap_or_else(|e| e.to_string())
+    } else if std::env::args().any(|arg| arg == "--csv") {
+        story.to_csv()
+    } else {
+        story.to_string()
+    }
+}
+
+fn stream_book() -> io::Result<FrequencyCounter> {
+    let mut counter = FrequencyCounter::new();
+    counter.read(io::BufReader::new(File::open(BOOK_PATH)?))?;
+    Ok(counter)
+}
+
 fn get_prize(score: u32) -> vehicles::cars::Car {
     match score {
         0 => vehicles::cars::Car::new_ferrari(),
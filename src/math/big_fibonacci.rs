@@ -0,0 +1,133 @@
+use super::{fib, fib_index_estimate, fib_inverse};
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use std::f64::consts::LN_2;
+use std::mem;
+
+pub struct BigFibonacciSequence {
+    previous: (BigUint, BigUint),
+    n: u64,
+}
+
+/// Same as fib but never overflows, falls back to BigFibonacciSequence past u64
+/// # Example
+/// ```
+/// let fib_num = fib_big(100);
+/// assert_eq!(fib_num.to_string(), "354224848179261915075");
+/// ```
+pub fn fib_big(n: u64) -> BigUint {
+    match fib(n) {
+        Some(v) => BigUint::from(v),
+        None => BigFibonacciSequence::new()
+            .nth(n as usize)
+            .unwrap_or_default(),
+    }
+}
+
+/// Same as fib_inverse but accepts values past u64
+pub fn fib_inverse_big(n: &BigUint) -> Option<u64> {
+    match n.to_u64() {
+        Some(v) => fib_inverse(v),
+        None => {
+            let estimate = fib_index_estimate(ln_big(n));
+            (estimate.saturating_sub(1)..=estimate + 1).find(|&index| fib_big(index) == *n)
+        }
+    }
+}
+
+/// Natural log of n taken from its top 64 bits so it stays finite past f64::MAX
+fn ln_big(n: &BigUint) -> f64 {
+    let shift = n.bits().saturating_sub(64);
+    let top = (n >> shift).to_f64().unwrap_or_default();
+    top.ln() + shift as f64 * LN_2
+}
+
+/// Returns (fib(n), fib(n + 1)) using the same fast doubling identities as fib_pair
+fn fib_pair_big(n: u64) -> (BigUint, BigUint) {
+    if n == 0 {
+        return (BigUint::zero(), BigUint::one());
+    }
+    let (a, b) = fib_pair_big(n / 2);
+    let c = &a * (&b * 2u8 - &a);
+    let d = &a * &a + &b * &b;
+    if n.is_multiple_of(2) {
+        (c, d)
+    } else {
+        let e = &c + &d;
+        (d, e)
+    }
+}
+
+impl BigFibonacciSequence {
+    pub fn new() -> BigFibonacciSequence {
+        BigFibonacciSequence {
+            previous: (BigUint::zero(), BigUint::one()),
+            n: 0,
+        }
+    }
+}
+
+impl Iterator for BigFibonacciSequence {
+    type Item = BigUint;
+    fn next(&mut self) -> Option<BigUint> {
+        let next = &self.previous.0 + &self.previous.1;
+        let current = mem::replace(&mut self.previous.1, next);
+        self.n += 1;
+        Some(mem::replace(&mut self.previous.0, current))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<BigUint> {
+        self.n += n as u64;
+        self.previous = fib_pair_big(self.n);
+        self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FibonacciSequence;
+    use super::*;
+
+    const FIB_1000: &str = "43466557686937456435688527675040625802564660517371780402481729089536555417949051890403879840079255169295922593080322634775209689623239873322471161642996440906533187938298969649928516003704476137795166849228875";
+
+    #[test]
+    fn big_fib_matches_u64_sequence() {
+        let big = BigFibonacciSequence::new();
+        for (expected, actual) in FibonacciSequence::new().zip(big) {
+            assert_eq!(actual, BigUint::from(expected));
+        }
+    }
+
+    #[test]
+    fn big_fib_does_not_overflow() {
+        let n = BigFibonacciSequence::new().nth(94).unwrap();
+        assert_eq!(n.to_string(), "19740274219868223167");
+    }
+
+    #[test]
+    fn fib_big_thousand() {
+        assert_eq!(fib_big(1000).to_string(), FIB_1000);
+    }
+
+    #[test]
+    fn fib_inverse_big_round_trips() {
+        for n in [0u64, 10, 93, 94, 95, 1000, 2500, 100_000] {
+            assert_eq!(fib_inverse_big(&fib_big(n)), Some(n));
+        }
+    }
+
+    #[test]
+    fn big_fib_nth_continues_sequence() {
+        let mut iterator = BigFibonacciSequence::new();
+        iterator.next();
+        assert_eq!(iterator.nth(9), Some(BigUint::from(55u8)));
+        assert_eq!(iterator.next(), Some(BigUint::from(89u8)));
+        assert_eq!(iterator.nth(988).unwrap().to_string(), FIB_1000);
+    }
+
+    #[test]
+    fn fib_inverse_big_rejects_non_fib() {
+        let n = fib_big(1000) + 1u8;
+        assert_eq!(fib_inverse_big(&n), None);
+    }
+}
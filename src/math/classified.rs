@@ -0,0 +1,193 @@
+use super::letter_freq;
+use hashbrown::HashMap;
+use unicode_general_category::{get_general_category, GeneralCategory};
+use unicode_script::{Script, UnicodeScript};
+
+/// The broad Unicode general category of a grapheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CharCategory {
+    Letter,
+    Mark,
+    Number,
+    Punctuation,
+    Symbol,
+    Whitespace,
+    Other,
+}
+
+/// The script and category of a grapheme, taken from its first character
+/// with a real script so that accents and other marks follow their base
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GraphemeClass {
+    pub script: Script,
+    pub category: CharCategory,
+}
+
+/// Grapheme counts like letter_freq with each grapheme's class alongside
+#[derive(Debug, Default)]
+pub struct ClassifiedFreq {
+    entries: HashMap<String, (GraphemeClass, u32)>,
+}
+
+pub fn classify(grapheme: &str) -> GraphemeClass {
+    let first = grapheme.chars().next().unwrap_or(' ');
+    let script = grapheme
+        .chars()
+        .map(|c| c.script())
+        .find(|script| !matches!(script, Script::Common | Script::Inherited))
+        .unwrap_or_else(|| first.script());
+    GraphemeClass {
+        script,
+        category: char_category(first),
+    }
+}
+
+pub fn classified_letter_freq<S: Into<String>>(text: S) -> ClassifiedFreq {
+    let entries = letter_freq(text)
+        .into_iter()
+        .map(|(grapheme, count)| {
+            let class = classify(&grapheme);
+            (grapheme, (class, count))
+        })
+        .collect();
+    ClassifiedFreq { entries }
+}
+
+impl ClassifiedFreq {
+    /// Counts of the graphemes whose class passes keep
+    pub fn filter<F: Fn(&GraphemeClass) -> bool>(&self, keep: F) -> HashMap<String, u32> {
+        self.entries
+            .iter()
+            .filter(|(_, (class, _))| keep(class))
+            .map(|(grapheme, (_, count))| (grapheme.to_string(), *count))
+            .collect()
+    }
+    /// Only the graphemes that are letters, in any script
+    pub fn letters(&self) -> HashMap<String, u32> {
+        self.filter(|class| class.category == CharCategory::Letter)
+    }
+    pub fn by_script(&self) -> HashMap<Script, HashMap<String, u32>> {
+        self.group(|class| class.script)
+    }
+    pub fn by_category(&self) -> HashMap<CharCategory, HashMap<String, u32>> {
+        self.group(|class| class.category)
+    }
+    /// Total count per script, most common first
+    pub fn script_totals(&self) -> Vec<(Script, u32)> {
+        let mut totals = self
+            .by_script()
+            .into_iter()
+            .map(|(script, freq)| (script, freq.values().sum::<u32>()))
+            .collect::<Vec<(Script, u32)>>();
+        totals.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| a.0.full_name().cmp(b.0.full_name()))
+        });
+        totals
+    }
+    fn group<K, F>(&self, key: F) -> HashMap<K, HashMap<String, u32>>
+    where
+        K: Eq + std::hash::Hash,
+        F: Fn(&GraphemeClass) -> K,
+    {
+        let mut groups = HashMap::new();
+        for (grapheme, (class, count)) in &self.entries {
+            let group = groups.entry(key(class)).or_insert_with(HashMap::new);
+            group.insert(grapheme.to_string(), *count);
+        }
+        groups
+    }
+}
+
+fn char_category(c: char) -> CharCategory {
+    use GeneralCategory::*;
+    match get_general_category(c) {
+        UppercaseLetter | LowercaseLetter | TitlecaseLetter | ModifierLetter | OtherLetter => {
+            CharCategory::Letter
+        }
+        NonspacingMark | SpacingMark | EnclosingMark => CharCategory::Mark,
+        DecimalNumber | LetterNumber | OtherNumber => CharCategory::Number,
+        ConnectorPunctuation | DashPunctuation | OpenPunctuation | ClosePunctuation
+        | InitialPunctuation | FinalPunctuation | OtherPunctuation => CharCategory::Punctuation,
+        MathSymbol | CurrencySymbol | ModifierSymbol | OtherSymbol => CharCategory::Symbol,
+        SpaceSeparator | LineSeparator | ParagraphSeparator => CharCategory::Whitespace,
+        _ if c.is_whitespace() => CharCategory::Whitespace,
+        _ => CharCategory::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_graphemes() {
+        let cases = [
+            ("a", Script::Latin, CharCategory::Letter),
+            ("é", Script::Latin, CharCategory::Letter),
+            ("e\u{301}", Script::Latin, CharCategory::Letter),
+            ("ж", Script::Cyrillic, CharCategory::Letter),
+            ("京", Script::Han, CharCategory::Letter),
+            ("カ", Script::Katakana, CharCategory::Letter),
+            ("か", Script::Hiragana, CharCategory::Letter),
+            ("7", Script::Common, CharCategory::Number),
+            (",", Script::Common, CharCategory::Punctuation),
+            ("。", Script::Common, CharCategory::Punctuation),
+            ("$", Script::Common, CharCategory::Symbol),
+            (" ", Script::Common, CharCategory::Whitespace),
+            ("\r\n", Script::Common, CharCategory::Whitespace),
+            ("\u{200b}", Script::Common, CharCategory::Other),
+        ];
+        for (grapheme, script, category) in cases {
+            assert_eq!(
+                classify(grapheme),
+                GraphemeClass { script, category },
+                "{:?}",
+                grapheme
+            );
+        }
+    }
+
+    #[test]
+    fn letters_leave_out_everything_else() {
+        let freq = classified_letter_freq("Hi, Ann! 42 кот");
+        let letters = freq.letters();
+        assert_eq!(letters.len(), 7);
+        assert_eq!(letters["n"], 2);
+        assert_eq!(letters.get(" "), None);
+        assert_eq!(letters.get("4"), None);
+        let digits = freq.filter(|class| class.category == CharCategory::Number);
+        assert_eq!(digits.len(), 2);
+    }
+
+    #[test]
+    fn groups_by_script_and_category() {
+        let freq = classified_letter_freq("旅ロ京 かば. Лорем");
+        let scripts = freq.by_script();
+        assert_eq!(scripts[&Script::Han].len(), 2);
+        assert_eq!(scripts[&Script::Katakana]["ロ"], 1);
+        assert_eq!(scripts[&Script::Hiragana].len(), 2);
+        assert_eq!(scripts[&Script::Cyrillic].len(), 5);
+        assert_eq!(scripts[&Script::Common].len(), 2);
+        let categories = freq.by_category();
+        assert_eq!(categories[&CharCategory::Whitespace][" "], 2);
+        assert_eq!(categories[&CharCategory::Punctuation]["."], 1);
+        assert_eq!(
+            freq.script_totals(),
+            vec![
+                (Script::Cyrillic, 5),
+                (Script::Common, 3),
+                (Script::Han, 2),
+                (Script::Hiragana, 2),
+                (Script::Katakana, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn letters_match_letter_freq_on_plain_words() {
+        let text = "abcabcxyz";
+        assert_eq!(classified_letter_freq(text).letters(), letter_freq(text));
+        assert!(classified_letter_freq("").by_script().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use hashbrown::HashMap;
+
+/// A collection of documents as word frequency maps, such as those from word_freq
+#[derive(Debug, Default)]
+pub struct Corpus {
+    documents: Vec<HashMap<String, u32>>,
+    document_freq: HashMap<String, u32>,
+}
+
+/// A word scored by how much more often one text uses it than another
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistinctiveWord {
+    pub word: String,
+    /// Log-odds ratio of the word in the target versus the other text
+    pub log_odds: f64,
+    /// The log-odds ratio divided by its standard deviation, so rare words
+    /// need a bigger difference to rank highly
+    pub z_score: f64,
+}
+
+impl Corpus {
+    pub fn new() -> Corpus {
+        Default::default()
+    }
+    /// Adds a document and returns its index
+    pub fn add(&mut self, freq: HashMap<String, u32>) -> usize {
+        for word in freq.keys() {
+            let count = self.document_freq.entry(word.to_string()).or_insert(0);
+            *count += 1;
+        }
+        self.documents.push(freq);
+        self.documents.len() - 1
+    }
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+    /// ln(documents / documents containing the word), 0 for unseen words
+    pub fn idf(&self, word: &str) -> f64 {
+        match self.document_freq.get(word) {
+            Some(&df) => (self.len() as f64 / df as f64).ln(),
+            None => 0.0,
+        }
+    }
+    /// Scores every word in a document by its share of the document times its idf
+    pub fn tf_idf(&self, doc: usize) -> Option<HashMap<String, f64>> {
+        let freq = self.documents.get(doc)?;
+        let total = freq.values().sum::<u32>() as f64;
+        Some(
+            freq.iter()
+                .map(|(word, count)| (word.to_string(), *count as f64 / total * self.idf(word)))
+                .collect(),
+        )
+    }
+    /// The k words with the highest tf-idf in a document, highest first
+    pub fn top_tf_idf(&self, doc: usize, k: usize) -> Option<Vec<(String, f64)>> {
+        let mut scores = self
+            .tf_idf(doc)?
+            .into_iter()
+            .collect::<Vec<(String, f64)>>();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        scores.truncate(k);
+        Some(scores)
+    }
+    /// Compares a document against every other document in the corpus combined
+    pub fn distinctive_words(&self, doc: usize) -> Option<Vec<DistinctiveWord>> {
+        let target = self.documents.get(doc)?;
+        let mut rest = HashMap::new();
+        for (index, freq) in self.documents.iter().enumerate() {
+            if index != doc {
+                super::merge_freq(&mut rest, freq.clone());
+            }
+        }
+        Some(distinctive_words(target, &rest))
+    }
+}
+
+/// Ranks every word used by either text by the log-odds ratio with an
+/// informative Dirichlet prior (Monroe, Colaresi and Quinn 2008), taking
+/// the two texts combined as the prior. Words most typical of target come
+/// first and words most typical of other come last.
+pub fn distinctive_words(
+    target: &HashMap<String, u32>,
+    other: &HashMap<String, u32>,
+) -> Vec<DistinctiveWord> {
+    let target_total = target.values().sum::<u32>() as f64;
+    let other_total = other.values().sum::<u32>() as f64;
+    let prior_total = target_total + other_total;
+    let mut combined = target.clone();
+    super::merge_freq(&mut combined, other.clone());
+    let mut found = combined
+        .into_iter()
+        .filter_map(|(word, prior)| {
+            let prior = prior as f64;
+            let in_target = *target.get(&word).unwrap_or(&0) as f64 + prior;
+            let in_other = *other.get(&word).unwrap_or(&0) as f64 + prior;
+            let log_odds = (in_target / (target_total + prior_total - in_target)).ln()
+                - (in_other / (other_total + prior_total - in_other)).ln();
+            let z_score = log_odds / (1.0 / in_target + 1.0 / in_other).sqrt();
+            z_score.is_finite().then_some(DistinctiveWord {
+                word,
+                log_odds,
+                z_score,
+            })
+        })
+        .collect::<Vec<DistinctiveWord>>();
+    found.sort_by(|a, b| {
+        b.z_score
+            .total_cmp(&a.z_score)
+            .then_with(|| a.word.cmp(&b.word))
+    });
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::word_freq;
+    use super::*;
+    use float_cmp::approx_eq;
+
+    fn corpus(texts: &[&str]) -> Corpus {
+        let mut corpus = Corpus::new();
+        for text in texts {
+            corpus.add(word_freq(*text));
+        }
+        corpus
+    }
+
+    #[test]
+    fn idf_counts_documents() {
+        let corpus = corpus(&["the cat sat", "the dog sat", "the cat ran"]);
+        assert_eq!(corpus.len(), 3);
+        assert!(approx_eq!(f64, corpus.idf("the"), 0.0));
+        assert!(approx_eq!(f64, corpus.idf("cat"), 1.5f64.ln()));
+        assert!(approx_eq!(f64, corpus.idf("dog"), 3f64.ln()));
+        assert!(approx_eq!(f64, corpus.idf("bird"), 0.0));
+    }
+
+    #[test]
+    fn tf_idf_weights_share_of_document() {
+        let corpus = corpus(&["the cat sat", "the dog dog sat"]);
+        let scores = corpus.tf_idf(1).unwrap();
+        assert!(approx_eq!(f64, scores["the"], 0.0));
+        assert!(approx_eq!(f64, scores["dog"], 0.5 * 2f64.ln()));
+        assert_eq!(corpus.tf_idf(2), None);
+        let top = corpus.top_tf_idf(0, 1).unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, "cat");
+    }
+
+    #[test]
+    fn distinctive_words_are_symmetric() {
+        let darcy = word_freq("Darcy said that Darcy was proud and Darcy left");
+        let elizabeth = word_freq("Elizabeth said that Elizabeth was not proud");
+        let found = distinctive_words(&darcy, &elizabeth);
+        assert_eq!(found.first().unwrap().word, "darcy");
+        assert_eq!(found.last().unwrap().word, "elizabeth");
+        let reversed = distinctive_words(&elizabeth, &darcy);
+        for word in found {
+            let mirror = reversed.iter().find(|w| w.word == word.word).unwrap();
+            assert!(approx_eq!(
+                f64,
+                word.z_score,
+                -mirror.z_score,
+                epsilon = 1e-9
+            ));
+        }
+    }
+
+    #[test]
+    fn shared_words_are_not_distinctive() {
+        let text = word_freq("the cat sat on the mat");
+        for word in distinctive_words(&text, &text) {
+            assert!(approx_eq!(f64, word.z_score, 0.0));
+        }
+        assert!(distinctive_words(&HashMap::new(), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn corpus_compares_against_the_rest() {
+        let corpus = corpus(&["the whale whale swam", "the ship sailed", "the ship sank"]);
+        let found = corpus.distinctive_words(0).unwrap();
+        assert_eq!(found.first().unwrap().word, "whale");
+        assert_eq!(found.last().unwrap().word, "ship");
+        assert_eq!(corpus.distinctive_words(3), None);
+    }
+}
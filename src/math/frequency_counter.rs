@@ -0,0 +1,206 @@
+use super::{count_letters, count_words, merge_freq};
+use hashbrown::HashMap;
+use std::io::{self, BufRead};
+use std::str;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Counts the same word and letter frequencies as word_freq and letter_freq
+/// but accepts text a chunk at a time, so the whole text never has to be in memory.
+/// The last two word boundary segments are held back until the next chunk
+/// arrives, since a word or grapheme may continue into them. Word boundaries
+/// never split a grapheme, and "a." or "1," only join a word once the next
+/// character is known, so every earlier boundary is final.
+/// Holding back more than that is always safe, so while a single long word or
+/// run of spaces has nothing ready to count the pending text is only segmented
+/// again once it has doubled, which keeps the total work linear.
+#[derive(Default)]
+pub struct FrequencyCounter {
+    words: HashMap<String, u32>,
+    letters: HashMap<String, u32>,
+    pending: String,
+    /// Length of the pending text when segmenting it last found nothing ready
+    stuck: usize,
+}
+
+impl FrequencyCounter {
+    pub fn new() -> FrequencyCounter {
+        FrequencyCounter::default()
+    }
+    pub fn push_str(&mut self, chunk: &str) {
+        self.pending.push_str(chunk);
+        if self.pending.len() < 2 * self.stuck {
+            return;
+        }
+        let split = self
+            .pending
+            .split_word_bound_indices()
+            .nth_back(1)
+            .map_or(0, |(index, _)| index);
+        if split > 0 {
+            let ready = self.pending.drain(..split).collect::<String>();
+            self.count(&ready);
+            self.stuck = 0;
+        } else {
+            self.stuck = self.pending.len();
+        }
+    }
+    /// Counts everything the reader produces, decoding UTF-8 across buffer boundaries
+    pub fn read<R: BufRead>(&mut self, mut reader: R) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            bytes.extend_from_slice(buffer);
+            let length = buffer.len();
+            reader.consume(length);
+            let valid = match str::from_utf8(&bytes) {
+                Ok(text) => text.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+            let rest = bytes.split_off(valid);
+            self.push_str(str::from_utf8(&bytes).map_err(invalid_data)?);
+            bytes = rest;
+        }
+        str::from_utf8(&bytes).map_err(invalid_data)?;
+        self.flush();
+        Ok(())
+    }
+    /// Counts any held back text, call once the input has ended
+    pub fn flush(&mut self) {
+        let ready = std::mem::take(&mut self.pending);
+        self.stuck = 0;
+        self.count(&ready);
+    }
+    pub fn merge(&mut self, mut other: FrequencyCounter) {
+        self.flush();
+        other.flush();
+        merge_freq(&mut self.words, other.words);
+        merge_freq(&mut self.letters, other.letters);
+    }
+    /// Returns the word and letter frequencies
+    pub fn finish(mut self) -> (HashMap<String, u32>, HashMap<String, u32>) {
+        self.flush();
+        (self.words, self.letters)
+    }
+    fn count(&mut self, text: &str) {
+        count_words(text, &mut self.words);
+        count_letters(text, &mut self.letters);
+    }
+}
+
+fn invalid_data(e: str::Utf8Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{letter_freq, word_freq};
+    use super::*;
+    use crate::test_support::BOOK;
+    use std::io::BufReader;
+
+    fn assert_matches_whole_text(counter: FrequencyCounter, text: &str) {
+        let (words, letters) = counter.finish();
+        assert_eq!(words, word_freq(text));
+        assert_eq!(letters, letter_freq(text));
+    }
+
+    #[test]
+    fn push_str_matches_whole_text() {
+        let chars = BOOK.chars().collect::<Vec<char>>();
+        let mut counter = FrequencyCounter::new();
+        for chunk in chars.chunks(7) {
+            counter.push_str(&chunk.iter().collect::<String>());
+        }
+        assert_matches_whole_text(counter, BOOK);
+    }
+
+    #[test]
+    fn read_matches_whole_text() {
+        let mut counter = FrequencyCounter::new();
+        let reader = BufReader::with_capacity(5, BOOK.as_bytes());
+        counter.read(reader).unwrap();
+        assert_matches_whole_text(counter, BOOK);
+    }
+
+    #[test]
+    fn words_split_across_chunks_are_joined() {
+        let mut counter = FrequencyCounter::new();
+        for chunk in ["Pri", "de and Pre", "j", "udice\r", "\n", "Café e\u{301}"] {
+            counter.push_str(chunk);
+        }
+        assert_matches_whole_text(counter, "Pride and Prejudice\r\nCafé e\u{301}");
+    }
+
+    #[test]
+    fn text_without_spaces_is_not_held_back() {
+        let japanese = include_str!("../../data/languages/ja.txt");
+        let mut counter = FrequencyCounter::new();
+        let mut text = String::new();
+        for _ in 0..200 {
+            for chunk in japanese.split_inclusive(['、', '。']) {
+                let chunk = chunk.trim();
+                counter.push_str(chunk);
+                text.push_str(chunk);
+                assert!(counter.pending.len() < 64, "{:?}", counter.pending);
+            }
+        }
+        assert!(!text.contains(char::is_whitespace));
+        assert_matches_whole_text(counter, &text);
+    }
+
+    #[test]
+    fn words_joined_by_punctuation_across_chunks() {
+        let mut counter = FrequencyCounter::new();
+        for chunk in [
+            "e.",
+            "g. 3,",
+            "5 can't",
+            "\u{1F1EB}",
+            "\u{1F1F7}\u{1F1E9}",
+            "\u{1F1EA}",
+        ] {
+            counter.push_str(chunk);
+        }
+        assert_matches_whole_text(
+            counter,
+            "e.g. 3,5 can't\u{1F1EB}\u{1F1F7}\u{1F1E9}\u{1F1EA}",
+        );
+    }
+
+    #[test]
+    fn long_text_without_boundaries_is_counted() {
+        let word = "a".repeat(1 << 18);
+        let spaces = " ".repeat(1 << 18);
+        let text = format!("{} {}{}b", word, word, spaces);
+        let mut counter = FrequencyCounter::new();
+        for chunk in text.as_bytes().chunks(1024) {
+            counter.push_str(str::from_utf8(chunk).unwrap());
+        }
+        assert_matches_whole_text(counter, &text);
+    }
+
+    #[test]
+    fn merge_adds_counts() {
+        let split = BOOK.match_indices(' ').nth(60_000).unwrap().0;
+        let (first, second) = BOOK.split_at(split);
+        let mut counter = FrequencyCounter::new();
+        counter.push_str(first);
+        let mut other = FrequencyCounter::new();
+        other.push_str(second);
+        counter.merge(other);
+        assert_matches_whole_text(counter, BOOK);
+    }
+
+    #[test]
+    fn read_rejects_invalid_utf8() {
+        let mut counter = FrequencyCounter::new();
+        let error = counter.read(&b"valid \xff text"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = counter.read(&b"truncated \xe6\x97"[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
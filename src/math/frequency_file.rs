@@ -0,0 +1,330 @@
+use super::merge_freq;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use hashbrown::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Marks the start of a binary frequency table, followed by FORMAT_VERSION
+const MAGIC: &[u8; 4] = b"FREQ";
+const FORMAT_VERSION: u8 = 1;
+
+/// Writes a frequency table as MAGIC, FORMAT_VERSION, the entry count and then
+/// each key's byte length, UTF-8 bytes and count, all little endian u32s.
+/// Keys are sorted so the same table always writes the same bytes.
+pub fn write_freq<W: Write>(freq: &HashMap<String, u32>, mut writer: W) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_u8(FORMAT_VERSION)?;
+    writer.write_u32::<LittleEndian>(to_u32(freq.len())?)?;
+    for (key, count) in sorted(freq) {
+        writer.write_u32::<LittleEndian>(to_u32(key.len())?)?;
+        writer.write_all(key.as_bytes())?;
+        writer.write_u32::<LittleEndian>(*count)?;
+    }
+    writer.flush()
+}
+
+pub fn read_freq<R: Read>(mut reader: R) -> io::Result<HashMap<String, u32>> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a frequency table"));
+    }
+    let version = reader.read_u8()?;
+    if version != FORMAT_VERSION {
+        return Err(invalid_data(format!("unsupported version {}", version)));
+    }
+    let entries = reader.read_u32::<LittleEndian>()?;
+    let mut freq = HashMap::new();
+    for _ in 0..entries {
+        let length = reader.read_u32::<LittleEndian>()? as u64;
+        let mut key = Vec::new();
+        reader.by_ref().take(length).read_to_end(&mut key)?;
+        if key.len() as u64 != length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let key = String::from_utf8(key).map_err(invalid_data)?;
+        let count = reader.read_u32::<LittleEndian>()?;
+        freq.insert(key, count);
+    }
+    Ok(freq)
+}
+
+/// Writes one key<TAB>count line per entry, escaping backslashes, tabs and
+/// line breaks in keys so letter tables with whitespace survive the trip
+pub fn write_freq_tsv<W: Write>(freq: &HashMap<String, u32>, mut writer: W) -> io::Result<()> {
+    for (key, count) in sorted(freq) {
+        writeln!(writer, "{}\t{}", escape(key), count)?;
+    }
+    writer.flush()
+}
+
+pub fn read_freq_tsv<R: BufRead>(reader: R) -> io::Result<HashMap<String, u32>> {
+    let mut freq = HashMap::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let (key, count) = line
+            .rsplit_once('\t')
+            .and_then(|(key, count)| Some((unescape(key)?, count.parse::<u32>().ok()?)))
+            .ok_or_else(|| invalid_data(format!("bad entry on line {}", index + 1)))?;
+        freq.insert(key, count);
+    }
+    Ok(freq)
+}
+
+/// Saves as TSV when the path ends in .tsv and in the binary format otherwise.
+/// The table is written to a temporary file next to path and then renamed over
+/// it, so a failed or interrupted save leaves the previous table in place.
+pub fn save_freq<P: AsRef<Path>>(freq: &HashMap<String, u32>, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?
+        .to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(name);
+    match write_file(freq, is_tsv(path), &temp) {
+        Ok(()) => fs::rename(&temp, path),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+fn write_file(freq: &HashMap<String, u32>, tsv: bool, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if tsv {
+        write_freq_tsv(freq, &mut writer)?;
+    } else {
+        write_freq(freq, &mut writer)?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+}
+
+pub fn load_freq<P: AsRef<Path>>(path: P) -> io::Result<HashMap<String, u32>> {
+    let reader = BufReader::new(File::open(&path)?);
+    if is_tsv(path.as_ref()) {
+        read_freq_tsv(reader)
+    } else {
+        read_freq(reader)
+    }
+}
+
+/// Loads a saved table, adds freq to it and saves it back, starting from
+/// nothing if the file does not exist yet
+pub fn merge_into_file<P: AsRef<Path>>(
+    freq: HashMap<String, u32>,
+    path: P,
+) -> io::Result<HashMap<String, u32>> {
+    let mut saved = match load_freq(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        result => result?,
+    };
+    merge_freq(&mut saved, freq);
+    save_freq(&saved, &path)?;
+    Ok(saved)
+}
+
+/// How much each count changed from old to new, leaving out unchanged keys
+pub fn diff_freq(old: &HashMap<String, u32>, new: &HashMap<String, u32>) -> HashMap<String, i64> {
+    let mut diff = HashMap::new();
+    for (key, count) in new {
+        let change = *count as i64 - *old.get(key).unwrap_or(&0) as i64;
+        if change != 0 {
+            diff.insert(key.to_string(), change);
+        }
+    }
+    for (key, count) in old {
+        if !new.contains_key(key) {
+            diff.insert(key.to_string(), -(*count as i64));
+        }
+    }
+    diff
+}
+
+/// Applies a diff from diff_freq, dropping keys whose count reaches zero
+pub fn apply_freq_diff(freq: &mut HashMap<String, u32>, diff: &HashMap<String, i64>) {
+    for (key, change) in diff {
+        let count = *freq.get(key).unwrap_or(&0) as i64 + change;
+        if count > 0 {
+            freq.insert(key.to_string(), count.min(u32::MAX as i64) as u32);
+        } else {
+            freq.remove(key);
+        }
+    }
+}
+
+fn sorted(freq: &HashMap<String, u32>) -> Vec<(&String, &u32)> {
+    let mut entries = freq.iter().collect::<Vec<(&String, &u32)>>();
+    entries.sort();
+    entries
+}
+
+fn is_tsv(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "tsv")
+}
+
+fn to_u32(n: usize) -> io::Result<u32> {
+    u32::try_from(n).map_err(invalid_data)
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn escape(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(key: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(key.len());
+    let mut chars = key.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{letter_freq, word_freq};
+    use super::*;
+    use crate::test_support::{counts, temp_path, BOOK};
+
+    #[test]
+    fn binary_round_trips() {
+        let freq = letter_freq(BOOK);
+        let mut bytes = Vec::new();
+        write_freq(&freq, &mut bytes).unwrap();
+        assert_eq!(read_freq(bytes.as_slice()).unwrap(), freq);
+        let mut again = Vec::new();
+        write_freq(&freq, &mut again).unwrap();
+        assert_eq!(bytes, again);
+    }
+
+    #[test]
+    fn binary_layout_is_little_endian() {
+        let mut bytes = Vec::new();
+        write_freq(&counts(&[("hé", 258)]), &mut bytes).unwrap();
+        assert_eq!(
+            bytes,
+            b"FREQ\x01\x01\x00\x00\x00\x03\x00\x00\x00h\xc3\xa9\x02\x01\x00\x00"
+        );
+    }
+
+    #[test]
+    fn binary_rejects_bad_input() {
+        let mut bytes = Vec::new();
+        write_freq(&counts(&[("cat", 2), ("dog", 1)]), &mut bytes).unwrap();
+        let truncated = read_freq(&bytes[..bytes.len() - 6]).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        let e = read_freq(wrong_magic.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 9;
+        let e = read_freq(wrong_version.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let mut bad_utf8 = bytes;
+        bad_utf8[13] = 0xff;
+        let e = read_freq(bad_utf8.as_slice()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tsv_round_trips_with_escapes() {
+        let freq = counts(&[("a", 3), ("\t", 1), ("\r\n", 2), ("\\", 4), (" ", 5)]);
+        let mut text = Vec::new();
+        write_freq_tsv(&freq, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert!(text.contains("\\r\\n\t2\n"));
+        assert_eq!(read_freq_tsv(text.as_bytes()).unwrap(), freq);
+        let words = word_freq(BOOK);
+        let mut text = Vec::new();
+        write_freq_tsv(&words, &mut text).unwrap();
+        assert_eq!(read_freq_tsv(text.as_slice()).unwrap(), words);
+    }
+
+    #[test]
+    fn tsv_rejects_bad_lines() {
+        for bad in ["cat\n", "cat\tmany\n", "bad\\q\t1\n", "a\t1\nb\t-2\n"] {
+            let e = read_freq_tsv(bad.as_bytes()).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn diff_and_apply_update_a_snapshot() {
+        let old = counts(&[("cat", 2), ("dog", 1), ("eel", 4)]);
+        let new = counts(&[("cat", 5), ("eel", 4), ("fox", 1)]);
+        let diff = diff_freq(&old, &new);
+        let expected = [("cat", 3i64), ("dog", -1), ("fox", 1)]
+            .iter()
+            .map(|(k, v)| (k.to_string(), *v))
+            .collect::<HashMap<String, i64>>();
+        assert_eq!(diff, expected);
+        let mut updated = old;
+        apply_freq_diff(&mut updated, &diff);
+        assert_eq!(updated, new);
+        assert!(diff_freq(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn files_pick_format_and_merge() {
+        for name in ["words.freq", "words.tsv"] {
+            let path = temp_path(name);
+            let first = merge_into_file(counts(&[("cat", 1)]), &path).unwrap();
+            assert_eq!(first, counts(&[("cat", 1)]));
+            let second = merge_into_file(counts(&[("cat", 2), ("dog", 1)]), &path).unwrap();
+            assert_eq!(second, counts(&[("cat", 3), ("dog", 1)]));
+            assert_eq!(load_freq(&path).unwrap(), second);
+            let text = std::fs::read(&path).unwrap();
+            assert_eq!(text.starts_with(b"FREQ"), name.ends_with(".freq"));
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn failed_save_keeps_the_old_table() {
+        let path = temp_path("kept.freq");
+        save_freq(&counts(&[("cat", 1)]), &path).unwrap();
+        let mut name = path.file_name().unwrap().to_os_string();
+        name.push(format!(".{}.tmp", std::process::id()));
+        let temp = path.with_file_name(name);
+        std::fs::create_dir(&temp).unwrap();
+        assert!(merge_into_file(counts(&[("dog", 1)]), &path).is_err());
+        assert_eq!(load_freq(&path).unwrap(), counts(&[("cat", 1)]));
+        std::fs::remove_dir(&temp).unwrap();
+        save_freq(&counts(&[("dog", 2)]), &path).unwrap();
+        assert_eq!(load_freq(&path).unwrap(), counts(&[("dog", 2)]));
+        assert!(!temp.exists());
+        std::fs::remove_file(path).unwrap();
+        assert!(save_freq(&counts(&[]), "/").is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use super::{letter_freq, letter_ngram_freq};
+use hashbrown::HashMap;
+
+/// Sample texts the default detector builds its profiles from
+const REFERENCE_SAMPLES: [(&str, &str); 10] = [
+    ("de", include_str!("../../data/languages/de.txt")),
+    ("en", include_str!("../../data/languages/en.txt")),
+    ("es", include_str!("../../data/languages/es.txt")),
+    ("fr", include_str!("../../data/languages/fr.txt")),
+    ("it", include_str!("../../data/languages/it.txt")),
+    ("ja", include_str!("../../data/languages/ja.txt")),
+    ("nl", include_str!("../../data/languages/nl.txt")),
+    ("pt", include_str!("../../data/languages/pt.txt")),
+    ("ru", include_str!("../../data/languages/ru.txt")),
+    ("zh", include_str!("../../data/languages/zh.txt")),
+];
+
+/// How much trigram similarity counts towards a score, letters make up the rest
+const TRIGRAM_WEIGHT: f64 = 0.7;
+
+/// Softmax temperature turning similarities into confidences, a language
+/// whose similarity is higher by this much is e times as likely
+const TEMPERATURE: f64 = 0.01;
+
+/// A language a text might be written in. Confidences of all the guesses
+/// for a text add up to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageGuess {
+    pub language: String,
+    pub confidence: f64,
+}
+
+/// Guesses languages by comparing letter and letter trigram counts against
+/// a profile per language using cosine similarity
+pub struct LanguageDetector {
+    profiles: Vec<(String, Profile)>,
+}
+
+struct Profile {
+    letters: HashMap<String, u32>,
+    letters_norm: f64,
+    trigrams: HashMap<String, u32>,
+    trigrams_norm: f64,
+}
+
+impl Profile {
+    /// Leaves out spaces, digits and punctuation which every language shares
+    fn new(text: &str) -> Profile {
+        let mut letters = letter_freq(text);
+        letters.retain(|letter, _| letter.chars().any(char::is_alphabetic));
+        let mut trigrams = letter_ngram_freq(text, 3);
+        trigrams.retain(|trigram, _| trigram.chars().any(char::is_alphabetic));
+        Profile {
+            letters_norm: norm(&letters),
+            letters,
+            trigrams_norm: norm(&trigrams),
+            trigrams,
+        }
+    }
+    fn similarity(&self, other: &Profile) -> f64 {
+        let letters = cosine(
+            &self.letters,
+            self.letters_norm,
+            &other.letters,
+            other.letters_norm,
+        );
+        let trigrams = cosine(
+            &self.trigrams,
+            self.trigrams_norm,
+            &other.trigrams,
+            other.trigrams_norm,
+        );
+        (1.0 - TRIGRAM_WEIGHT) * letters + TRIGRAM_WEIGHT * trigrams
+    }
+}
+
+impl Default for LanguageDetector {
+    fn default() -> LanguageDetector {
+        let mut detector = LanguageDetector::empty();
+        for (language, sample) in REFERENCE_SAMPLES {
+            detector.add_profile(language, sample);
+        }
+        detector
+    }
+}
+
+impl LanguageDetector {
+    /// A detector with the bundled reference profiles
+    pub fn new() -> LanguageDetector {
+        Default::default()
+    }
+    /// A detector with no profiles to add your own to
+    pub fn empty() -> LanguageDetector {
+        LanguageDetector {
+            profiles: Vec::new(),
+        }
+    }
+    pub fn add_profile<S: Into<String>>(&mut self, language: S, sample: &str) {
+        self.profiles.push((language.into(), Profile::new(sample)));
+    }
+    /// Ranks every language with any similarity to the text, most likely first,
+    /// with a softmax over the similarities as the confidence
+    pub fn detect(&self, text: &str) -> Vec<LanguageGuess> {
+        let profile = Profile::new(text);
+        let scores = self
+            .profiles
+            .iter()
+            .map(|(language, reference)| (language, profile.similarity(reference)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect::<Vec<(&String, f64)>>();
+        let best = scores.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+        let weights = scores
+            .into_iter()
+            .map(|(language, score)| (language, ((score - best) / TEMPERATURE).exp()))
+            .collect::<Vec<(&String, f64)>>();
+        let total = weights.iter().map(|(_, weight)| weight).sum::<f64>();
+        let mut guesses = weights
+            .into_iter()
+            .map(|(language, weight)| LanguageGuess {
+                language: language.to_string(),
+                confidence: weight / total,
+            })
+            .collect::<Vec<LanguageGuess>>();
+        guesses.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        guesses
+    }
+}
+
+fn norm(freq: &HashMap<String, u32>) -> f64 {
+    freq.values()
+        .map(|count| (*count as f64).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn cosine(a: &HashMap<String, u32>, a_norm: f64, b: &HashMap<String, u32>, b_norm: f64) -> f64 {
+    if a_norm == 0.0 || b_norm == 0.0 {
+        return 0.0;
+    }
+    let (small, large) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    let dot = small
+        .iter()
+        .filter_map(|(key, count)| Some(*count as f64 * *large.get(key)? as f64))
+        .sum::<f64>();
+    dot / (a_norm * b_norm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::BOOK;
+    use float_cmp::approx_eq;
+
+    fn best(detector: &LanguageDetector, text: &str) -> String {
+        detector.detect(text)[0].language.to_string()
+    }
+
+    #[test]
+    fn detects_bundled_languages() {
+        let detector = LanguageDetector::new();
+        let samples = [
+            (
+                "en",
+                "The quick brown fox jumps over the lazy dog and runs away.",
+            ),
+            (
+                "fr",
+                "Le renard brun rapide saute par-dessus le chien paresseux.",
+            ),
+            (
+                "de",
+                "Der schnelle braune Fuchs springt über den faulen Hund.",
+            ),
+            (
+                "es",
+                "El rápido zorro marrón salta sobre el perro perezoso.",
+            ),
+            ("it", "La rapida volpe marrone salta sopra il cane pigro."),
+            ("nl", "De snelle bruine vos springt over de luie hond heen."),
+            ("pt", "A rápida raposa marrom pula sobre o cão preguiçoso."),
+            (
+                "ru",
+                "Быстрая коричневая лиса прыгает через ленивую собаку.",
+            ),
+            ("ja", "素早い茶色の狐がのろまな犬を飛び越えました。"),
+            ("zh", "敏捷的棕色狐狸跳过了那只懒狗。"),
+        ];
+        for (language, text) in samples {
+            assert_eq!(best(&detector, text), language, "{}", text);
+        }
+    }
+
+    #[test]
+    fn confidences_are_ranked_and_sum_to_one() {
+        let guesses = LanguageDetector::new().detect("Лорем ипсум долор сит амет");
+        assert_eq!(guesses[0].language, "ru");
+        assert!(guesses[0].confidence > 0.9, "{:?}", guesses);
+        for pair in guesses.windows(2) {
+            assert!(pair[0].confidence >= pair[1].confidence);
+        }
+        let total = guesses.iter().map(|g| g.confidence).sum::<f64>();
+        assert!(approx_eq!(f64, total, 1.0, epsilon = 1e-9));
+    }
+
+    #[test]
+    fn confidence_reflects_the_margin() {
+        let detector = LanguageDetector::new();
+        let english = detector.detect("The quick brown fox jumps over the lazy dog and runs away.");
+        assert!(english[0].confidence > 0.99, "{:?}", english);
+        let portuguese = detector.detect("A rápida raposa marrom pula sobre o cão preguiçoso.");
+        assert_eq!(portuguese[1].language, "es");
+        assert!(portuguese[0].confidence > 0.7, "{:?}", portuguese);
+        assert!(portuguese[0].confidence > 3.0 * portuguese[1].confidence);
+        assert!(portuguese[1].confidence > 0.1, "{:?}", portuguese);
+    }
+
+    #[test]
+    fn detects_the_book_as_english() {
+        let start = BOOK.chars().take(20_000).collect::<String>();
+        let guesses = LanguageDetector::new().detect(&start);
+        assert_eq!(guesses[0].language, "en");
+    }
+
+    #[test]
+    fn unknown_text_has_no_guesses() {
+        let detector = LanguageDetector::new();
+        assert!(detector.detect("").is_empty());
+        assert!(detector.detect("ᚠᚢᚦᚨᚱᚲ").is_empty());
+        assert!(LanguageDetector::empty().detect("hello").is_empty());
+    }
+
+    #[test]
+    fn custom_profiles_are_used() {
+        let mut detector = LanguageDetector::empty();
+        detector.add_profile("runic", "ᚠᚢᚦᚨᚱᚲ ᚷᚹᚺᚾᛁᛃ");
+        detector.add_profile("latin", "lorem ipsum dolor sit amet");
+        assert_eq!(best(&detector, "ᚠᚢᚦ ᚨᚱᚲ"), "runic");
+        assert_eq!(detector.detect("ᚠᚢᚦ").len(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use hashbrown::HashSet;
+use std::collections::VecDeque;
+
+/// A sequence where each term after the seeds is a weighted sum of the terms
+/// before it, a(n) = coefficients[0] * a(n - 1) + coefficients[1] * a(n - 2) + ...
+/// Like FibonacciSequence it stops yielding once the next term overflows u64.
+/// Sequences that never grow, like 1, 1, 1, ..., go on forever.
+#[derive(Clone)]
+pub struct LinearRecurrence {
+    seeds: Vec<u64>,
+    coefficients: Vec<u64>,
+    window: VecDeque<u64>,
+    n: usize,
+}
+
+impl LinearRecurrence {
+    /// None if there are no coefficients, as every term would then be 0
+    pub fn new(seeds: Vec<u64>, coefficients: Vec<u64>) -> Option<LinearRecurrence> {
+        if coefficients.is_empty() {
+            return None;
+        }
+        Some(LinearRecurrence::with(seeds, coefficients))
+    }
+    pub fn fibonacci() -> LinearRecurrence {
+        LinearRecurrence::with(vec![0, 1], vec![1, 1])
+    }
+    pub fn lucas() -> LinearRecurrence {
+        LinearRecurrence::with(vec![2, 1], vec![1, 1])
+    }
+    pub fn pell() -> LinearRecurrence {
+        LinearRecurrence::with(vec![0, 1], vec![2, 1])
+    }
+    pub fn tribonacci() -> LinearRecurrence {
+        LinearRecurrence::with(vec![0, 0, 1], vec![1, 1, 1])
+    }
+    fn with(seeds: Vec<u64>, coefficients: Vec<u64>) -> LinearRecurrence {
+        LinearRecurrence {
+            window: VecDeque::from(vec![0; coefficients.len()]),
+            seeds,
+            coefficients,
+            n: 0,
+        }
+    }
+}
+
+impl LinearRecurrence {
+    /// Finds the first index v such that a(v) >= n, scanning until the sequence
+    /// overflows or starts repeating itself
+    pub fn inverse_rounded_up(&self, n: u64) -> Option<u64> {
+        self.scan().position(|value| value >= n).map(|v| v as u64)
+    }
+    /// Finds the first index v such that a(v) = n, scanning until the sequence
+    /// overflows or starts repeating itself
+    /// # Example
+    /// ```
+    /// let index = LinearRecurrence::lucas().inverse(29).unwrap();
+    /// assert_eq!(index, 7);
+    /// ```
+    pub fn inverse(&self, n: u64) -> Option<u64> {
+        self.scan().position(|value| value == n).map(|v| v as u64)
+    }
+    /// The terms from the start, ending early once the window of recent terms
+    /// is one seen before past the seeds, as from there the terms only repeat
+    fn scan(&self) -> impl Iterator<Item = u64> {
+        let mut sequence = LinearRecurrence::with(self.seeds.clone(), self.coefficients.clone());
+        let mut seen = HashSet::new();
+        std::iter::from_fn(move || {
+            if sequence.n >= sequence.seeds.len() && !seen.insert(sequence.window.clone()) {
+                return None;
+            }
+            sequence.next()
+        })
+    }
+    fn next_term(&self) -> Option<u64> {
+        self.coefficients
+            .iter()
+            .zip(self.window.iter().rev())
+            .try_fold(0u64, |sum, (c, v)| c.checked_mul(*v)?.checked_add(sum))
+    }
+}
+
+impl Iterator for LinearRecurrence {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let value = match self.seeds.get(self.n) {
+            Some(seed) => *seed,
+            None => self.next_term()?,
+        };
+        self.window.pop_front();
+        self.window.push_back(value);
+        self.n += 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FibonacciSequence;
+    use super::*;
+
+    fn first_ten(sequence: LinearRecurrence) -> Vec<u64> {
+        sequence.take(10).collect()
+    }
+
+    #[test]
+    fn fibonacci_matches_fibonacci_sequence() {
+        let expected = FibonacciSequence::new().collect::<Vec<u64>>();
+        let actual = LinearRecurrence::fibonacci().collect::<Vec<u64>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn named_sequences_are_correct() {
+        assert_eq!(
+            first_ten(LinearRecurrence::lucas()),
+            vec![2, 1, 3, 4, 7, 11, 18, 29, 47, 76]
+        );
+        assert_eq!(
+            first_ten(LinearRecurrence::pell()),
+            vec![0, 1, 2, 5, 12, 29, 70, 169, 408, 985]
+        );
+        assert_eq!(
+            first_ten(LinearRecurrence::tribonacci()),
+            vec![0, 0, 1, 1, 2, 4, 7, 13, 24, 44]
+        );
+    }
+
+    #[test]
+    fn custom_seeds_and_coefficients() {
+        let jacobsthal = LinearRecurrence::new(vec![0, 1], vec![1, 2]).unwrap();
+        assert_eq!(
+            first_ten(jacobsthal),
+            vec![0, 1, 1, 3, 5, 11, 21, 43, 85, 171]
+        );
+        let powers_of_three = LinearRecurrence::new(vec![1], vec![3]).unwrap();
+        assert_eq!(powers_of_three.count(), 41);
+    }
+
+    #[test]
+    fn overflow_stops_sequence() {
+        let mut pell = LinearRecurrence::pell();
+        let last = pell.by_ref().last().unwrap();
+        assert!(last.checked_mul(3).is_none());
+        assert_eq!(pell.next(), None);
+        assert_eq!(pell.next(), None);
+    }
+
+    #[test]
+    fn inverse_finds_first_index() {
+        let lucas = LinearRecurrence::lucas();
+        assert_eq!(lucas.inverse(2), Some(0));
+        assert_eq!(lucas.inverse(1), Some(1));
+        assert_eq!(lucas.inverse(29), Some(7));
+        assert_eq!(lucas.inverse(30), None);
+        assert_eq!(lucas.inverse(u64::MAX), None);
+    }
+
+    #[test]
+    fn inverse_rounded_up_finds_first_index_at_or_above() {
+        let pell = LinearRecurrence::pell();
+        assert_eq!(pell.inverse_rounded_up(0), Some(0));
+        assert_eq!(pell.inverse_rounded_up(12), Some(4));
+        assert_eq!(pell.inverse_rounded_up(13), Some(5));
+        assert_eq!(pell.inverse_rounded_up(u64::MAX), None);
+    }
+
+    #[test]
+    fn rejects_empty_coefficients() {
+        assert!(LinearRecurrence::new(vec![1, 2], Vec::new()).is_none());
+        assert!(LinearRecurrence::new(Vec::new(), Vec::new()).is_none());
+    }
+
+    #[test]
+    fn inverse_stops_on_sequences_that_never_grow() {
+        let ones = LinearRecurrence::new(vec![1], vec![1]).unwrap();
+        assert_eq!(ones.inverse(1), Some(0));
+        assert_eq!(ones.inverse(5), None);
+        assert_eq!(ones.inverse_rounded_up(2), None);
+        let zeros = LinearRecurrence::new(Vec::new(), vec![3, 2]).unwrap();
+        assert_eq!(zeros.inverse(0), Some(0));
+        assert_eq!(zeros.inverse(1), None);
+        let alternating = LinearRecurrence::new(vec![1, 2, 7], vec![0, 1]).unwrap();
+        assert_eq!(
+            first_ten(alternating.clone()),
+            vec![1, 2, 7, 2, 7, 2, 7, 2, 7, 2]
+        );
+        assert_eq!(alternating.inverse(7), Some(2));
+        assert_eq!(alternating.inverse(3), None);
+        assert_eq!(alternating.inverse_rounded_up(5), Some(2));
+        assert_eq!(alternating.inverse_rounded_up(8), None);
+    }
+
+    #[test]
+    fn inverse_matches_fib_inverse() {
+        let fibonacci = LinearRecurrence::fibonacci();
+        for n in 0..1000 {
+            assert_eq!(fibonacci.inverse(n), super::super::fib_inverse(n));
+            assert_eq!(
+                fibonacci.inverse_rounded_up(n),
+                super::super::fib_inverse_rounded_up(n)
+            );
+        }
+    }
+}
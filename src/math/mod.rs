@@ -1,18 +1,53 @@
+mod big_fibonacci;
+mod classified;
+mod corpus;
+mod frequency_counter;
+mod frequency_file;
+mod language;
+mod linear_recurrence;
+mod ngrams;
+mod normalization;
+mod parallel;
+mod primes;
+mod zeckendorf;
+
+pub use big_fibonacci::*;
+pub use classified::*;
+pub use corpus::*;
+pub use frequency_counter::*;
+pub use frequency_file::*;
+pub use language::*;
+pub use linear_recurrence::*;
+pub use ngrams::*;
+pub use normalization::*;
+pub use parallel::*;
+pub use zeckendorf::*;
+
 use hashbrown::HashMap;
+use primes::{factorize, gcd};
 use unicode_segmentation::UnicodeSegmentation;
 
 pub struct FibonacciSequence {
-    previous: (u64, u64),
-    n: u8,
+    upcoming: (Option<u64>, Option<u64>),
+    n: u64,
 }
 
+const MAX_FIB_INDEX: u64 = 93;
+
 pub fn fib(n: u64) -> Option<u64> {
     let mut fib_it = FibonacciSequence::new();
     fib_it.nth(n as usize)
 }
 
 pub fn fib_inverse_rounded_up(n: u64) -> Option<u64> {
-    FibonacciSequence::new().position(|value| value >= n).map(|v| v as u64)
+    let mut index = fib_index_estimate((n as f64).ln()).min(MAX_FIB_INDEX);
+    while index > 0 && fib(index - 1)? >= n {
+        index -= 1;
+    }
+    while fib(index)? < n {
+        index += 1;
+    }
+    Some(index)
 }
 
 /// Finds the number v such that fib(v) = n given n
@@ -22,13 +57,121 @@ pub fn fib_inverse_rounded_up(n: u64) -> Option<u64> {
 /// assert_eq!(fib_num, 10);
 /// ```
 pub fn fib_inverse(n: u64) -> Option<u64> {
-    FibonacciSequence::new().position(|value| value == n).map(|v| v as u64)
+    let index = fib_inverse_rounded_up(n)?;
+    (fib(index)? == n).then_some(index)
+}
+
+/// Estimates v such that fib(v) = n given ln(n) using Binet's formula
+/// fib(v) ≈ φ^v / √5, so v ≈ (ln(n) + ln(√5)) / ln(φ)
+fn fib_index_estimate(ln_n: f64) -> u64 {
+    let sqrt_5 = 5f64.sqrt();
+    let golden_ratio = (1.0 + sqrt_5) / 2.0;
+    ((ln_n + sqrt_5.ln()) / golden_ratio.ln()).round() as u64
+}
+
+/// Returns (fib(n), fib(n + 1)) using the fast doubling identities
+/// fib(2k) = fib(k) * (2 * fib(k + 1) - fib(k))
+/// fib(2k + 1) = fib(k)² + fib(k + 1)²
+fn fib_pair(n: u64) -> (u128, u128) {
+    if n == 0 {
+        return (0, 1);
+    }
+    let (a, b) = fib_pair(n / 2);
+    let c = a * (2 * b - a);
+    let d = a * a + b * b;
+    if n.is_multiple_of(2) {
+        (c, d)
+    } else {
+        (d, c + d)
+    }
+}
+
+/// Finds fib(n) mod m without overflowing, even when fib(n) itself would
+/// # Example
+/// ```
+/// let fib_num = fib_mod(1_000_000_000_000_000_000, 1_000_000_007).unwrap();
+/// assert_eq!(fib_num, 209783453);
+/// ```
+pub fn fib_mod(n: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    Some(fib_pair_mod(n as u128, m as u128).0 as u64)
+}
+
+/// Finds the period with which fib(n) mod m repeats, None if m is 0 or the
+/// period does not fit in a u64. The period of m is the lcm of the periods of
+/// its prime powers, each found by trimming a known multiple of it.
+pub fn pisano_period(m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    let period = factorize(m)
+        .into_iter()
+        .map(|(p, k)| prime_power_pisano_period(p, k))
+        .fold(1, |period, other| period / gcd_u128(period, other) * other);
+    u64::try_from(period).ok()
+}
+
+/// The period of p^k divides p^(k - 1) times the period of p, which is 3 for 2,
+/// 20 for 5, divides p - 1 when p mod 5 is 1 or 4 and divides 2(p + 1) otherwise.
+/// Dividing out primes while fib still repeats leaves the exact period.
+fn prime_power_pisano_period(p: u64, k: u32) -> u128 {
+    let (mut period, mut primes) = match p {
+        2 => (3, vec![3]),
+        5 => (20, vec![2, 5]),
+        _ if p % 5 == 1 || p % 5 == 4 => (p as u128 - 1, prime_factors(p - 1)),
+        _ => {
+            let mut primes = prime_factors(p + 1);
+            primes.push(2);
+            (2 * (p as u128 + 1), primes)
+        }
+    };
+    let m = (p as u128).pow(k);
+    if k > 1 {
+        period *= m / p as u128;
+        primes.push(p);
+    }
+    for q in primes {
+        let q = q as u128;
+        while period.is_multiple_of(q) && fib_pair_mod(period / q, m) == (0, 1 % m) {
+            period /= q;
+        }
+    }
+    period
+}
+
+fn prime_factors(n: u64) -> Vec<u64> {
+    factorize(n).into_iter().map(|(p, _)| p).collect()
+}
+
+fn gcd_u128(a: u128, b: u128) -> u128 {
+    match (u64::try_from(a), u64::try_from(b)) {
+        (Ok(a), Ok(b)) => gcd(a, b) as u128,
+        _ if b == 0 => a,
+        _ => gcd_u128(b, a % b),
+    }
+}
+
+/// Same as fib_pair with every step reduced mod m
+fn fib_pair_mod(n: u128, m: u128) -> (u128, u128) {
+    if n == 0 {
+        return (0, 1 % m);
+    }
+    let (a, b) = fib_pair_mod(n / 2, m);
+    let c = a * ((2 * b + m - a) % m) % m;
+    let d = (a * a % m + b * b % m) % m;
+    if n.is_multiple_of(2) {
+        (c, d)
+    } else {
+        (d, (c + d) % m)
+    }
 }
 
 impl FibonacciSequence {
     pub fn new() -> FibonacciSequence {
         FibonacciSequence {
-            previous: (1, 1),
+            upcoming: (Some(0), Some(1)),
             n: 0,
         }
     }
@@ -37,43 +180,59 @@ impl FibonacciSequence {
 impl Iterator for FibonacciSequence {
     type Item = u64;
     fn next(&mut self) -> Option<u64> {
+        let value = self.upcoming.0?;
+        self.upcoming = (
+            self.upcoming.1,
+            self.upcoming.1.and_then(|v| v.checked_add(value)),
+        );
         self.n += 1;
-        match self.n {
-            1 => Some(0),
-            2 | 3 => Some(1),
-            _ => match self.previous.0.checked_add(self.previous.1) {
-                Some(v) => {
-                    self.n = 3;
-                    self.previous.0 = self.previous.1;
-                    self.previous.1 = v;
-                    Some(v)
-                }
-                None => None,
-            },
-        }
+        Some(value)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<u64> {
+        self.n = self.n.saturating_add(n as u64);
+        self.upcoming = if self.n > MAX_FIB_INDEX {
+            (None, None)
+        } else {
+            let (v, next) = fib_pair(self.n);
+            (Some(v as u64), u64::try_from(next).ok())
+        };
+        self.next()
     }
 }
 
 pub fn word_freq<S: Into<String>>(text: S) -> HashMap<String, u32> {
     let mut freq = HashMap::new();
-    let s = text.into();
-    let words = s.unicode_words().collect::<Vec<&str>>();
-    for w in words {
-        let count = freq.entry(w.to_lowercase()).or_insert(0);
-        *count += 1;
-    }
+    count_words(&text.into(), &mut freq);
     freq
 }
 
 pub fn letter_freq<S: Into<String>>(text: S) -> HashMap<String, u32> {
     let mut freq = HashMap::new();
-    let s = text.into();
-    let graphemes = UnicodeSegmentation::graphemes(s.as_str(), true).collect::<Vec<&str>>();
-    for l in graphemes {
+    count_letters(&text.into(), &mut freq);
+    freq
+}
+
+fn count_words(s: &str, freq: &mut HashMap<String, u32>) {
+    for w in s.unicode_words() {
+        let count = freq.entry(w.to_lowercase()).or_insert(0);
+        *count += 1;
+    }
+}
+
+fn count_letters(s: &str, freq: &mut HashMap<String, u32>) {
+    for l in UnicodeSegmentation::graphemes(s, true) {
         let count = freq.entry(l.to_lowercase()).or_insert(0);
         *count += 1;
     }
-    freq
+}
+
+/// Adds other's counts to freq, stopping at u32::MAX rather than overflowing
+pub fn merge_freq(freq: &mut HashMap<String, u32>, other: HashMap<String, u32>) {
+    for (key, value) in other {
+        let count = freq.entry(key).or_insert(0);
+        *count = count.saturating_add(value);
+    }
 }
 
 pub fn next_factor_of_three(x: i32) -> i32 {
@@ -121,4 +280,146 @@ mod tests {
         let n = FibonacciSequence::new().nth(93).unwrap();
         assert_eq!(n, 12200160415121876738);
     }
+
+    #[test]
+    fn fib_nth_matches_next() {
+        let expected_list = FibonacciSequence::new().collect::<Vec<u64>>();
+        assert_eq!(expected_list.len(), 94);
+        for (index, expected) in expected_list.iter().enumerate() {
+            assert_eq!(fib(index as u64), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn fib_nth_continues_sequence() {
+        let mut iterator = FibonacciSequence::new();
+        iterator.next();
+        assert_eq!(iterator.nth(9), Some(55));
+        assert_eq!(iterator.next(), Some(89));
+        assert_eq!(iterator.nth(81), Some(12200160415121876738));
+        assert_eq!(iterator.next(), None);
+    }
+
+    #[test]
+    fn fib_inverse_finds_every_index() {
+        for (index, value) in FibonacciSequence::new().enumerate().skip(3) {
+            assert_eq!(fib_inverse(value), Some(index as u64));
+        }
+        assert_eq!(fib_inverse(0), Some(0));
+        assert_eq!(fib_inverse(1), Some(1));
+    }
+
+    #[test]
+    fn fib_inverse_rejects_non_fib() {
+        assert_eq!(fib_inverse(4), None);
+        assert_eq!(fib_inverse(56), None);
+        assert_eq!(fib_inverse(u64::MAX), None);
+    }
+
+    #[test]
+    fn fib_mod_matches_sequence() {
+        for m in [1u64, 2, 3, 7, 10, 1000, 1_000_000_007, u64::MAX] {
+            for (index, value) in FibonacciSequence::new().enumerate() {
+                assert_eq!(fib_mod(index as u64, m), Some(value % m), "m = {}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn fib_mod_huge_n() {
+        let n = fib_mod(1_000_000_000_000_000_000, 1_000_000_007);
+        assert_eq!(n, Some(209783453));
+        let n = fib_mod(u64::MAX, u64::MAX - 58);
+        assert_eq!(n, Some(18446743708274255395));
+    }
+
+    #[test]
+    fn fib_mod_zero_modulus() {
+        assert_eq!(fib_mod(10, 0), None);
+        assert_eq!(pisano_period(0), None);
+    }
+
+    #[test]
+    fn pisano_period_known_values() {
+        let expected = [
+            (1u64, 1u64),
+            (2, 3),
+            (3, 8),
+            (5, 20),
+            (10, 60),
+            (100, 300),
+            (1000, 1500),
+        ];
+        for (m, period) in expected {
+            assert_eq!(pisano_period(m), Some(period), "m = {}", m);
+        }
+    }
+
+    #[test]
+    fn merge_freq_saturates() {
+        let mut freq = HashMap::from([("the".to_string(), u32::MAX - 1), ("a".to_string(), 1)]);
+        merge_freq(
+            &mut freq,
+            HashMap::from([("the".to_string(), 5), ("cat".to_string(), 2)]),
+        );
+        assert_eq!(freq["the"], u32::MAX);
+        assert_eq!(freq["a"], 1);
+        assert_eq!(freq["cat"], 2);
+    }
+
+    #[test]
+    fn pisano_period_matches_brute_force() {
+        for m in 1..2000u64 {
+            let start = (0, 1 % m);
+            let mut pair = start;
+            let mut period = 0;
+            loop {
+                pair = (pair.1, (pair.0 + pair.1) % m);
+                period += 1;
+                if pair == start {
+                    break;
+                }
+            }
+            assert_eq!(pisano_period(m), Some(period), "m = {}", m);
+        }
+    }
+
+    #[test]
+    fn pisano_period_large_moduli() {
+        assert_eq!(pisano_period(1_000_000_007), Some(2_000_000_016));
+        assert_eq!(pisano_period(998_244_353), Some(1_996_488_708));
+        for m in [u64::MAX, u64::MAX - 58, 1 << 63, 600_851_475_143] {
+            let period = pisano_period(m).unwrap();
+            assert_eq!(fib_mod(period, m), Some(0), "m = {}", m);
+            assert_eq!(fib_mod(period + 1, m), Some(1), "m = {}", m);
+            for (q, _) in factorize(period) {
+                let shorter = period / q;
+                assert!(
+                    fib_mod(shorter, m) != Some(0) || fib_mod(shorter + 1, m) != Some(1),
+                    "m = {}",
+                    m
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fib_mod_repeats_every_pisano_period() {
+        for m in 1..50u64 {
+            let period = pisano_period(m).unwrap();
+            for n in 0..100 {
+                assert_eq!(fib_mod(n, m), fib_mod(n + period, m), "m = {}", m);
+            }
+        }
+    }
+
+    #[test]
+    fn fib_inverse_rounded_up_matches_linear_search() {
+        for n in (0..2000).chain(12200160415121876730..12200160415121876740) {
+            let expected = FibonacciSequence::new()
+                .position(|value| value >= n)
+                .map(|v| v as u64);
+            assert_eq!(fib_inverse_rounded_up(n), expected, "n = {}", n);
+        }
+    }
 }
@@ -0,0 +1,172 @@
+use hashbrown::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A pair of adjacent words along with how strongly they are associated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collocation {
+    pub first: String,
+    pub second: String,
+    pub count: u32,
+    /// Pointwise mutual information in bits
+    pub pmi: f64,
+    /// Dunning's log-likelihood ratio, G²
+    pub log_likelihood: f64,
+}
+
+/// Counts runs of n adjacent words, lowercased like word_freq and joined by a space
+pub fn word_ngram_freq<S: Into<String>>(text: S, n: usize) -> HashMap<String, u32> {
+    ngram_freq(&words(&text.into()), n, " ")
+}
+
+/// Counts runs of n adjacent graphemes, lowercased like letter_freq
+pub fn letter_ngram_freq<S: Into<String>>(text: S, n: usize) -> HashMap<String, u32> {
+    let s = text.into();
+    let letters = UnicodeSegmentation::graphemes(s.as_str(), true)
+        .map(|l| l.to_lowercase())
+        .collect::<Vec<String>>();
+    ngram_freq(&letters, n, "")
+}
+
+/// Scores every word bigram seen at least min_count times, strongest first
+pub fn collocations<S: Into<String>>(text: S, min_count: u32) -> Vec<Collocation> {
+    let words = words(&text.into());
+    let mut pairs = HashMap::new();
+    let mut firsts = HashMap::new();
+    let mut seconds = HashMap::new();
+    for pair in words.windows(2) {
+        let count = pairs.entry((&pair[0], &pair[1])).or_insert(0u32);
+        *count += 1;
+        let count = firsts.entry(&pair[0]).or_insert(0u32);
+        *count += 1;
+        let count = seconds.entry(&pair[1]).or_insert(0u32);
+        *count += 1;
+    }
+    let total = words.len().saturating_sub(1) as f64;
+    let mut found = pairs
+        .into_iter()
+        .filter(|(_, count)| *count >= min_count)
+        .map(|((first, second), count)| {
+            let k11 = count as f64;
+            let row = firsts[first] as f64;
+            let column = seconds[second] as f64;
+            Collocation {
+                first: first.to_string(),
+                second: second.to_string(),
+                count,
+                pmi: (k11 * total / (row * column)).log2(),
+                log_likelihood: log_likelihood(k11, row, column, total),
+            }
+        })
+        .collect::<Vec<Collocation>>();
+    found.sort_by(|a, b| {
+        b.log_likelihood
+            .total_cmp(&a.log_likelihood)
+            .then_with(|| (&a.first, &a.second).cmp(&(&b.first, &b.second)))
+    });
+    found
+}
+
+fn words(s: &str) -> Vec<String> {
+    s.unicode_words().map(|w| w.to_lowercase()).collect()
+}
+
+fn ngram_freq(tokens: &[String], n: usize, separator: &str) -> HashMap<String, u32> {
+    let mut freq = HashMap::new();
+    if n == 0 {
+        return freq;
+    }
+    for window in tokens.windows(n) {
+        let count = freq.entry(window.join(separator)).or_insert(0);
+        *count += 1;
+    }
+    freq
+}
+
+/// G² = 2 Σ observed * ln(observed / expected) over the 2x2 contingency table
+fn log_likelihood(k11: f64, row: f64, column: f64, total: f64) -> f64 {
+    let observed = [
+        (k11, row, column),
+        (row - k11, row, total - column),
+        (column - k11, total - row, column),
+        (total - row - column + k11, total - row, total - column),
+    ];
+    2.0 * observed
+        .iter()
+        .filter(|(k, _, _)| *k > 0.0)
+        .map(|(k, r, c)| k * (k * total / (r * c)).ln())
+        .sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{letter_freq, word_freq};
+    use super::*;
+    use crate::test_support::BOOK;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn unigrams_match_word_and_letter_freq() {
+        let text = "It is a truth universally acknowledged, that a single man";
+        assert_eq!(word_ngram_freq(text, 1), word_freq(text));
+        assert_eq!(letter_ngram_freq(text, 1), letter_freq(text));
+    }
+
+    #[test]
+    fn word_ngrams_are_counted() {
+        let text = "The cat sat. The cat ran!";
+        let bigrams = word_ngram_freq(text, 2);
+        assert_eq!(bigrams.get("the cat"), Some(&2));
+        assert_eq!(bigrams.get("sat the"), Some(&1));
+        assert_eq!(bigrams.len(), 4);
+        let trigrams = word_ngram_freq(text, 3);
+        assert_eq!(trigrams.get("the cat sat"), Some(&1));
+        assert_eq!(trigrams.len(), 4);
+        assert!(word_ngram_freq(text, 0).is_empty());
+        assert!(word_ngram_freq(text, 7).is_empty());
+    }
+
+    #[test]
+    fn letter_ngrams_use_graphemes() {
+        let trigrams = letter_ngram_freq("Cafe\u{301} café", 3);
+        assert_eq!(trigrams.get("afe\u{301}"), Some(&1));
+        assert_eq!(trigrams.get("afé"), Some(&1));
+        assert_eq!(trigrams.len(), 6);
+    }
+
+    #[test]
+    fn collocation_scores() {
+        let found = collocations("a b a b a c d", 1);
+        let ab = found
+            .iter()
+            .find(|c| c.first == "a" && c.second == "b")
+            .unwrap();
+        assert_eq!(ab.count, 2);
+        // 6 bigrams, "a" starts 3 of them and "b" ends 2
+        assert!(approx_eq!(
+            f64,
+            ab.pmi,
+            (2.0f64 * 6.0 / 6.0).log2(),
+            ulps = 2
+        ));
+        let expected = 2.0
+            * (2.0 * (2.0f64 * 6.0 / 6.0).ln()
+                + 1.0 * (6.0f64 / 12.0).ln()
+                + 3.0 * (18.0f64 / 12.0).ln());
+        assert!(approx_eq!(f64, ab.log_likelihood, expected, epsilon = 1e-9));
+        assert_eq!(collocations("a b a b a c d", 2).len(), 2);
+    }
+
+    #[test]
+    fn book_collocations_find_names() {
+        let found = collocations(BOOK, 5);
+        let top = found
+            .iter()
+            .take(50)
+            .map(|c| format!("{} {}", c.first, c.second))
+            .collect::<Vec<String>>();
+        assert!(top.contains(&"mr darcy".to_string()), "{:?}", top);
+        for pair in found.windows(2) {
+            assert!(pair[0].log_likelihood >= pair[1].log_likelihood);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use caseless::default_case_fold_str;
+use hashbrown::{HashMap, HashSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+const ENGLISH_STOPWORDS: &str = "\
+    a about above after again against all am an and any are as at be because been before \
+    being below between both but by can did do does doing don't down during each few for from \
+    further had has have having he her here hers herself him himself his how i if in into is \
+    it it's its itself just me more most my myself no nor not now of off on once only or \
+    other our ours ourselves out over own same she should so some such than that the their \
+    theirs them themselves then there these they this those through to too under until up \
+    very was we were what when where which while who whom why will with you your yours \
+    yourself yourselves";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    Unchanged,
+    Nfc,
+    Nfkc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Unchanged,
+    Lowercase,
+    Fold,
+}
+
+/// Controls how text is cleaned up before word_freq_with and letter_freq_with count it.
+/// The default matches word_freq and letter_freq, which only lowercase.
+/// Stopwords are compared against words after they are normalized.
+#[derive(Debug, Clone)]
+pub struct NormalizationOptions {
+    pub form: NormalizationForm,
+    pub case: CaseMode,
+    pub strip_accents: bool,
+    pub stopwords: HashSet<String>,
+}
+
+impl Default for NormalizationOptions {
+    fn default() -> NormalizationOptions {
+        NormalizationOptions {
+            form: NormalizationForm::Unchanged,
+            case: CaseMode::Lowercase,
+            strip_accents: false,
+            stopwords: HashSet::new(),
+        }
+    }
+}
+
+impl NormalizationOptions {
+    fn normalize(&self, text: &str) -> String {
+        let text = if self.strip_accents {
+            let decomposed = match self.form {
+                NormalizationForm::Nfkc => text.nfkd().collect::<String>(),
+                _ => text.nfd().collect::<String>(),
+            };
+            decomposed
+                .chars()
+                .filter(|c| !is_combining_mark(*c))
+                .collect()
+        } else {
+            text.to_string()
+        };
+        match self.form {
+            // Stripping decomposes, so recompose whatever was not a mark like Hangul
+            NormalizationForm::Unchanged if self.strip_accents => text.nfc().collect(),
+            NormalizationForm::Unchanged => text,
+            NormalizationForm::Nfc => text.nfc().collect(),
+            NormalizationForm::Nfkc => text.nfkc().collect(),
+        }
+    }
+    fn apply_case(&self, s: &str) -> String {
+        match self.case {
+            CaseMode::Unchanged => s.to_string(),
+            CaseMode::Lowercase => s.to_lowercase(),
+            CaseMode::Fold => default_case_fold_str(s),
+        }
+    }
+}
+
+pub fn english_stopwords() -> HashSet<String> {
+    ENGLISH_STOPWORDS
+        .split_whitespace()
+        .map(|w| w.to_string())
+        .collect()
+}
+
+pub fn word_freq_with<S: Into<String>>(
+    text: S,
+    options: &NormalizationOptions,
+) -> HashMap<String, u32> {
+    let mut freq = HashMap::new();
+    let s = options.normalize(&text.into());
+    for w in s.unicode_words() {
+        let w = options.apply_case(w);
+        if !options.stopwords.contains(&w) {
+            let count = freq.entry(w).or_insert(0);
+            *count += 1;
+        }
+    }
+    freq
+}
+
+pub fn letter_freq_with<S: Into<String>>(
+    text: S,
+    options: &NormalizationOptions,
+) -> HashMap<String, u32> {
+    let mut freq = HashMap::new();
+    let s = options.normalize(&text.into());
+    for l in UnicodeSegmentation::graphemes(s.as_str(), true) {
+        let count = freq.entry(options.apply_case(l)).or_insert(0);
+        *count += 1;
+    }
+    freq
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{letter_freq, word_freq};
+    use super::*;
+    use crate::test_support::BOOK;
+
+    fn nfc() -> NormalizationOptions {
+        NormalizationOptions {
+            form: NormalizationForm::Nfc,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_options_match_word_freq() {
+        let options = NormalizationOptions::default();
+        assert_eq!(word_freq_with(BOOK, &options), word_freq(BOOK));
+        assert_eq!(letter_freq_with(BOOK, &options), letter_freq(BOOK));
+    }
+
+    #[test]
+    fn nfc_joins_composed_and_decomposed() {
+        let freq = word_freq_with("Café cafe\u{301}", &nfc());
+        assert_eq!(freq.len(), 1);
+        assert_eq!(freq.get("café"), Some(&2));
+        let freq = letter_freq_with("é e\u{301}", &nfc());
+        assert_eq!(freq.get("é"), Some(&2));
+        let freq = word_freq("Café cafe\u{301}");
+        assert_eq!(freq.len(), 2);
+    }
+
+    #[test]
+    fn nfkc_joins_compatibility_forms() {
+        let options = NormalizationOptions {
+            form: NormalizationForm::Nfkc,
+            ..Default::default()
+        };
+        let freq = word_freq_with("ﬁne fine ｆｉｎｅ", &options);
+        assert_eq!(freq.get("fine"), Some(&3));
+        assert_eq!(word_freq_with("ﬁne fine", &nfc()).len(), 2);
+    }
+
+    #[test]
+    fn strip_accents_removes_marks() {
+        let options = NormalizationOptions {
+            strip_accents: true,
+            ..nfc()
+        };
+        let freq = word_freq_with("Café cafe\u{301} cafe naïve", &options);
+        assert_eq!(freq.get("cafe"), Some(&3));
+        assert_eq!(freq.get("naive"), Some(&1));
+    }
+
+    #[test]
+    fn strip_accents_keeps_other_text_composed() {
+        let options = NormalizationOptions {
+            strip_accents: true,
+            ..Default::default()
+        };
+        let freq = word_freq_with("한국어 Café", &options);
+        assert_eq!(freq.get("한국어"), Some(&1));
+        assert_eq!(freq.get("cafe"), Some(&1));
+        let freq = letter_freq_with("한국어", &options);
+        assert_eq!(freq.get("한"), Some(&1));
+        assert_eq!(freq.len(), 3);
+    }
+
+    #[test]
+    fn case_modes() {
+        let text = "Straße STRASSE strasse";
+        let unchanged = NormalizationOptions {
+            case: CaseMode::Unchanged,
+            ..Default::default()
+        };
+        assert_eq!(word_freq_with(text, &unchanged).len(), 3);
+        let lowercase = word_freq_with(text, &NormalizationOptions::default());
+        assert_eq!(lowercase.get("strasse"), Some(&2));
+        assert_eq!(lowercase.get("straße"), Some(&1));
+        let fold = NormalizationOptions {
+            case: CaseMode::Fold,
+            ..Default::default()
+        };
+        assert_eq!(word_freq_with(text, &fold).get("strasse"), Some(&3));
+    }
+
+    #[test]
+    fn stopwords_are_excluded() {
+        let options = NormalizationOptions {
+            stopwords: english_stopwords(),
+            ..Default::default()
+        };
+        let freq = word_freq_with("It is a truth universally acknowledged", &options);
+        let mut words = freq.keys().cloned().collect::<Vec<String>>();
+        words.sort();
+        assert_eq!(words, vec!["acknowledged", "truth", "universally"]);
+        let freq = letter_freq_with("a the", &options);
+        assert_eq!(freq.get("a"), Some(&1));
+    }
+}
@@ -0,0 +1,140 @@
+use super::{count_letters, count_words, merge_freq};
+use hashbrown::HashMap;
+use std::thread;
+use unicode_segmentation::GraphemeCursor;
+
+/// Same as word_freq but splits the text across the given number of threads
+pub fn par_word_freq(text: &str, threads: usize) -> HashMap<String, u32> {
+    par_freq(text, threads, count_words)
+}
+
+/// Same as letter_freq but splits the text across the given number of threads
+pub fn par_letter_freq(text: &str, threads: usize) -> HashMap<String, u32> {
+    par_freq(text, threads, count_letters)
+}
+
+fn par_freq(
+    text: &str,
+    threads: usize,
+    count: fn(&str, &mut HashMap<String, u32>),
+) -> HashMap<String, u32> {
+    thread::scope(|scope| {
+        let handles = split_at_whitespace(text, threads)
+            .into_iter()
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut freq = HashMap::new();
+                    count(chunk, &mut freq);
+                    freq
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut freq = HashMap::new();
+        for handle in handles {
+            merge_freq(&mut freq, handle.join().expect("Counting thread panicked!"));
+        }
+        freq
+    })
+}
+
+/// Splits text into at most n roughly equal chunks, only cutting right before
+/// a run of whitespace since no word spans that point, and only where it is
+/// also a grapheme boundary since a Prepend character joins the space after it
+fn split_at_whitespace(text: &str, n: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    for remaining in (1..n).rev() {
+        let target = rest.len() / (remaining + 1);
+        if let Some(split) = whitespace_run_start(rest, target) {
+            let (chunk, tail) = rest.split_at(split);
+            chunks.push(chunk);
+            rest = tail;
+        }
+    }
+    chunks.push(rest);
+    chunks
+}
+
+/// Finds the start of the first run of whitespace that reaches past index
+/// and does not begin inside a grapheme
+fn whitespace_run_start(text: &str, index: usize) -> Option<usize> {
+    let mut index = (index..text.len()).find(|i| text.is_char_boundary(*i))?;
+    loop {
+        let space = index + text[index..].find(char::is_whitespace)?;
+        let start = text[..space].trim_end_matches(char::is_whitespace).len();
+        let mut cursor = GraphemeCursor::new(start, text.len(), true);
+        if cursor.is_boundary(text, 0) == Ok(true) {
+            return Some(start);
+        }
+        index = space + text[space..].chars().next()?.len_utf8();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{letter_freq, word_freq};
+    use super::*;
+    use crate::test_support::BOOK;
+    use std::time::Instant;
+
+    #[test]
+    fn par_word_freq_matches_word_freq() {
+        let expected = word_freq(BOOK);
+        for threads in [1, 3, 8] {
+            assert_eq!(par_word_freq(BOOK, threads), expected);
+        }
+    }
+
+    #[test]
+    fn par_letter_freq_matches_letter_freq() {
+        let expected = letter_freq(BOOK);
+        for threads in [1, 3, 8] {
+            assert_eq!(par_letter_freq(BOOK, threads), expected);
+        }
+    }
+
+    #[test]
+    fn par_freq_handles_short_text() {
+        let text = "Лорем  ипсум\r\nдолор";
+        for threads in [0, 1, 2, 50] {
+            assert_eq!(par_word_freq(text, threads), word_freq(text));
+            assert_eq!(par_letter_freq(text, threads), letter_freq(text));
+        }
+        let text = "ab\u{600} cd";
+        assert_eq!(par_letter_freq(text, 2), letter_freq(text));
+        assert_eq!(par_word_freq(text, 2), word_freq(text));
+        assert!(par_word_freq("", 4).is_empty());
+    }
+
+    #[test]
+    fn split_at_whitespace_keeps_words_whole() {
+        let chunks = split_at_whitespace(BOOK, 8);
+        assert_eq!(chunks.len(), 8);
+        assert_eq!(chunks.concat(), BOOK);
+        for chunk in &chunks[1..] {
+            assert!(chunk.starts_with(char::is_whitespace));
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_par_word_freq() {
+        let text = BOOK.repeat(8);
+        let threads = thread::available_parallelism().map_or(4, |n| n.get());
+        let start = Instant::now();
+        let expected = word_freq(text.as_str());
+        let single = start.elapsed();
+        let start = Instant::now();
+        let actual = par_word_freq(&text, threads);
+        let parallel = start.elapsed();
+        assert_eq!(actual, expected);
+        println!(
+            "word_freq {:?}, par_word_freq on {} threads {:?}, {:.2}x speedup",
+            single,
+            threads,
+            parallel,
+            single.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+}
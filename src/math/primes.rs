@@ -0,0 +1,182 @@
+/// Bases that make Miller-Rabin exact for every u64
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// The prime factors of n with their exponents, smallest first. Empty for 0 and 1.
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    if n > 1 {
+        split(n, &mut primes);
+    }
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((last, exponent)) if *last == p => *exponent += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+/// Deterministic Miller-Rabin
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let d = (n - 1) >> (n - 1).trailing_zeros();
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        let mut d = d;
+        while d != n - 1 {
+            x = mul_mod(x, x, n);
+            d *= 2;
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Pushes every prime factor of n > 1, with repeats
+fn split(n: u64, primes: &mut Vec<u64>) {
+    if is_prime(n) {
+        primes.push(n);
+        return;
+    }
+    for p in WITNESSES {
+        if n.is_multiple_of(p) {
+            primes.push(p);
+            if n > p {
+                split(n / p, primes);
+            }
+            return;
+        }
+    }
+    let divisor = (1..).find_map(|c| pollard_rho(n, c)).unwrap_or(n);
+    split(divisor, primes);
+    split(n / divisor, primes);
+}
+
+/// A nontrivial divisor of the odd composite n using Brent's variant of
+/// Pollard's rho with x² + c, None if this c fails
+fn pollard_rho(n: u64, c: u64) -> Option<u64> {
+    let step = |x: u64| ((x as u128 * x as u128 + c as u128) % n as u128) as u64;
+    let (mut y, mut divisor) = (2, 1);
+    let mut length = 1;
+    while divisor == 1 {
+        let x = y;
+        for _ in 0..length {
+            y = step(y);
+            divisor = gcd(x.abs_diff(y), n);
+            if divisor != 1 {
+                break;
+            }
+        }
+        length *= 2;
+    }
+    (divisor != n).then_some(divisor)
+}
+
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trial_division(mut n: u64) -> Vec<(u64, u32)> {
+        let mut factors = Vec::new();
+        let mut p = 2;
+        while p * p <= n {
+            let mut exponent = 0;
+            while n.is_multiple_of(p) {
+                n /= p;
+                exponent += 1;
+            }
+            if exponent > 0 {
+                factors.push((p, exponent));
+            }
+            p += 1;
+        }
+        if n > 1 {
+            factors.push((n, 1));
+        }
+        factors
+    }
+
+    #[test]
+    fn is_prime_matches_trial_division() {
+        for n in 0..5000u64 {
+            let expected = trial_division(n) == vec![(n, 1)];
+            assert_eq!(is_prime(n), expected, "n = {}", n);
+        }
+        assert!(is_prime(1_000_000_007));
+        assert!(is_prime(18446744073709551557));
+        assert!(!is_prime(u64::MAX));
+        assert!(!is_prime(3_215_031_751));
+    }
+
+    #[test]
+    fn factorizes_small_numbers() {
+        assert!(factorize(0).is_empty());
+        assert!(factorize(1).is_empty());
+        for n in 2..5000u64 {
+            assert_eq!(factorize(n), trial_division(n), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn factorizes_large_numbers() {
+        assert_eq!(
+            factorize(u64::MAX),
+            vec![
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65537, 1),
+                (6700417, 1)
+            ]
+        );
+        assert_eq!(
+            factorize(1_000_000_007 * 998_244_353),
+            vec![(998_244_353, 1), (1_000_000_007, 1)]
+        );
+        assert_eq!(factorize(1 << 63), vec![(2, 63)]);
+        assert_eq!(
+            factorize(4_294_967_291 * 4_294_967_291),
+            vec![(4_294_967_291, 2)]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use super::{fib, fib_inverse_rounded_up, MAX_FIB_INDEX};
+
+/// Splits n into a sum of non-consecutive Fibonacci numbers, largest first
+/// # Example
+/// ```
+/// let parts = zeckendorf(100);
+/// assert_eq!(parts, vec![89, 8, 3]);
+/// ```
+pub fn zeckendorf(n: u64) -> Vec<u64> {
+    zeckendorf_indices(n).into_iter().filter_map(fib).collect()
+}
+
+/// Encodes n >= 1 with the self-delimiting Fibonacci code, bit k marks fib(k + 2)
+/// and every code word ends in two consecutive ones
+pub fn fib_encode(n: u64) -> Option<Vec<bool>> {
+    let indices = zeckendorf_indices(n);
+    let mut bits = vec![false; *indices.first()? as usize - 1];
+    for index in indices {
+        bits[index as usize - 2] = true;
+    }
+    bits.push(true);
+    Some(bits)
+}
+
+/// Encodes every value and packs the code words into bytes, most significant bit first
+pub fn fib_encode_bytes(values: &[u64]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut length = 0;
+    for value in values {
+        for bit in fib_encode(*value)? {
+            if length % 8 == 0 {
+                bytes.push(0);
+            }
+            if bit {
+                *bytes.last_mut()? |= 0x80 >> (length % 8);
+            }
+            length += 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Decodes every complete code word packed by fib_encode_bytes
+pub fn fib_decode_bytes(bytes: &[u8]) -> Vec<u64> {
+    let bits = bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |shift| byte & (0x80 >> shift) != 0));
+    FibonacciDecoder::new(bits).collect()
+}
+
+/// Reads Fibonacci code words from a bit stream, stopping at the first
+/// incomplete or out of range code word
+pub struct FibonacciDecoder<I: Iterator<Item = bool>> {
+    bits: I,
+}
+
+impl<I: Iterator<Item = bool>> FibonacciDecoder<I> {
+    pub fn new(bits: I) -> FibonacciDecoder<I> {
+        FibonacciDecoder { bits }
+    }
+}
+
+impl<I: Iterator<Item = bool>> Iterator for FibonacciDecoder<I> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        let mut previous = false;
+        for index in 2.. {
+            let bit = self.bits.next()?;
+            if bit && previous {
+                return Some(value);
+            }
+            if bit {
+                value = value.checked_add(fib(index)?)?;
+            }
+            previous = bit;
+        }
+        None
+    }
+}
+
+/// Greedily picks the largest fib(v) <= n, returning the indices v >= 2 in descending order
+fn zeckendorf_indices(mut n: u64) -> Vec<u64> {
+    let mut indices = Vec::new();
+    while n > 0 {
+        let mut index = fib_inverse_rounded_up(n)
+            .unwrap_or(MAX_FIB_INDEX + 1)
+            .max(2);
+        if fib(index).is_none_or(|v| v > n) {
+            index -= 1;
+        }
+        n -= fib(index).unwrap_or(n);
+        indices.push(index);
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FibonacciSequence;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn interesting_values() -> Vec<u64> {
+        let mut rng = StdRng::seed_from_u64(1342);
+        let mut values = FibonacciSequence::new()
+            .flat_map(|v| [v.saturating_sub(1), v, v.saturating_add(1)])
+            .collect::<Vec<u64>>();
+        values.extend((1..1000).chain([u64::MAX - 1, u64::MAX]));
+        values.extend((0..1000).map(|_| rng.gen::<u64>()));
+        values.retain(|v| *v > 0);
+        values
+    }
+
+    fn to_bits(code: &str) -> Vec<bool> {
+        code.chars().map(|c| c == '1').collect()
+    }
+
+    #[test]
+    fn zeckendorf_sums_non_consecutive_fibs() {
+        for n in interesting_values() {
+            let parts = zeckendorf(n);
+            assert_eq!(parts.iter().sum::<u64>(), n);
+            let indices = zeckendorf_indices(n);
+            for pair in indices.windows(2) {
+                assert!(pair[0] > pair[1] + 1, "n = {}, parts = {:?}", n, parts);
+            }
+        }
+    }
+
+    #[test]
+    fn zeckendorf_of_zero_is_empty() {
+        assert!(zeckendorf(0).is_empty());
+        assert_eq!(fib_encode(0), None);
+    }
+
+    #[test]
+    fn fib_encode_known_code_words() {
+        let expected = [
+            (1u64, "11"),
+            (2, "011"),
+            (3, "0011"),
+            (4, "1011"),
+            (11, "001011"),
+            (65, "0100100011"),
+        ];
+        for (n, code) in expected {
+            assert_eq!(fib_encode(n), Some(to_bits(code)), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn fib_code_round_trips() {
+        let values = interesting_values();
+        let bits = values
+            .iter()
+            .flat_map(|v| fib_encode(*v).unwrap())
+            .collect::<Vec<bool>>();
+        let decoded = FibonacciDecoder::new(bits.into_iter()).collect::<Vec<u64>>();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn fib_code_bytes_round_trip() {
+        let values = interesting_values();
+        let bytes = fib_encode_bytes(&values).unwrap();
+        assert_eq!(fib_decode_bytes(&bytes), values);
+    }
+
+    #[test]
+    fn fib_decoder_stops_on_out_of_range_code_word() {
+        let bits = [true].into_iter().chain([false; 100]);
+        assert_eq!(FibonacciDecoder::new(bits).next(), None);
+    }
+}
@@ -0,0 +1,125 @@
+use super::{
+    read_story, top_k, FetchClient, ProgressSender, StoryError, StoryReport, StorySource,
+    StringCount,
+};
+use hashbrown::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Reports for a batch of stories in the order they were given, with word and
+/// letter counts summed over every story that was read successfully
+#[derive(Debug)]
+pub struct StoryBatch {
+    pub reports: Vec<Result<StoryReport, StoryError>>,
+    pub word_freq: HashMap<String, u32>,
+    pub letter_freq: HashMap<String, u32>,
+}
+
+impl StoryBatch {
+    pub fn top_words(&self, k: usize) -> Vec<StringCount> {
+        top_k(k, &self.word_freq)
+    }
+}
+
+/// Reads every source on its own task with at most `concurrency` of them
+/// fetching or analyzing at once. One failing source does not stop the rest.
+pub async fn read_stories(
+    sources: Vec<StorySource>,
+    client: &FetchClient,
+    concurrency: usize,
+) -> StoryBatch {
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let tasks = sources
+        .into_iter()
+        .map(|source| {
+            let client = client.clone();
+            let permits = permits.clone();
+            tokio::spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                read_story(source, &client, &ProgressSender::default()).await
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut batch = StoryBatch {
+        reports: Vec::with_capacity(tasks.len()),
+        word_freq: HashMap::new(),
+        letter_freq: HashMap::new(),
+    };
+    for task in tasks {
+        let result = task.await.expect("Story task panicked!");
+        if let Ok(report) = &result {
+            add_freq(&mut batch.word_freq, &report.word_freq);
+            add_freq(&mut batch.letter_freq, &report.letter_freq);
+        }
+        batch.reports.push(result);
+    }
+    batch
+}
+
+/// Like merge_freq but leaves the report's counts in place
+fn add_freq(total: &mut HashMap<String, u32>, freq: &HashMap<String, u32>) {
+    for (key, count) in freq {
+        match total.get_mut(key) {
+            Some(sum) => *sum = sum.saturating_add(*count),
+            None => {
+                total.insert(key.to_string(), *count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_server::{self, test_client};
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_every_source_in_order() {
+        let url = test_server::serve("200 OK", b"The dog saw the cat.").await;
+        let sources = vec![
+            StorySource::Text("The cat sat.".into()),
+            url.as_str().into(),
+            StorySource::Text("A cat.".into()),
+        ];
+        let batch = read_stories(sources, &test_client(0), 2).await;
+        let texts = batch
+            .reports
+            .iter()
+            .map(|report| report.as_ref().unwrap().text.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            texts,
+            vec!["The cat sat.", "The dog saw the cat.", "A cat."]
+        );
+        assert_eq!(batch.word_freq["cat"], 3);
+        assert_eq!(batch.word_freq["the"], 3);
+        assert_eq!(batch.word_freq.values().sum::<u32>(), 10);
+        assert_eq!(batch.letter_freq["c"], 3);
+        assert_eq!(
+            batch.top_words(2),
+            vec![StringCount("cat".into(), 3), StringCount("the".into(), 3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_going_after_failures() {
+        let missing = test_server::serve("404 Not Found", b"").await;
+        let sources = vec![
+            missing.as_str().into(),
+            StorySource::Text("Once upon a time".into()),
+            StorySource::Text("".into()),
+        ];
+        let batch = read_stories(sources, &test_client(0), 0).await;
+        assert!(matches!(batch.reports[0], Err(StoryError::Status(_))));
+        assert_eq!(batch.reports[1].as_ref().unwrap().word_count, 4);
+        assert!(matches!(batch.reports[2], Err(StoryError::Empty)));
+        assert_eq!(batch.word_freq.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn handles_no_sources() {
+        let batch = read_stories(Vec::new(), &test_client(0), 4).await;
+        assert!(batch.reports.is_empty());
+        assert!(batch.word_freq.is_empty());
+    }
+}
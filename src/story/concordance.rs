@@ -0,0 +1,198 @@
+use hashbrown::HashMap;
+use std::fmt;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Where every word of a text occurs, split and lowercased like word_freq
+pub struct Concordance {
+    text: String,
+    spans: Vec<Range<usize>>,
+    positions: HashMap<String, Vec<usize>>,
+}
+
+/// One match with the text around it cut to the context width and with
+/// runs of whitespace shown as single spaces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KwicLine {
+    /// Index of the first matched word among all the words of the text
+    pub position: usize,
+    pub left: String,
+    pub keyword: String,
+    pub right: String,
+    width: usize,
+}
+
+impl Concordance {
+    pub fn new(text: String) -> Concordance {
+        let mut spans = Vec::new();
+        let mut positions = HashMap::new();
+        for (start, word) in text.unicode_word_indices() {
+            let list = positions
+                .entry(word.to_lowercase())
+                .or_insert_with(Vec::new);
+            list.push(spans.len());
+            spans.push(start..start + word.len());
+        }
+        Concordance {
+            text,
+            spans,
+            positions,
+        }
+    }
+    pub fn word_count(&self) -> usize {
+        self.spans.len()
+    }
+    /// Word indices where the word occurs, in order
+    pub fn positions(&self, word: &str) -> &[usize] {
+        self.positions
+            .get(&word.to_lowercase())
+            .map_or(&[], |list| list.as_slice())
+    }
+    /// Word indices where every word of the phrase occurs one after another,
+    /// ignoring case and whatever punctuation or spacing lies between them
+    pub fn find_phrase(&self, phrase: &str) -> Vec<usize> {
+        let words = phrase
+            .unicode_words()
+            .map(|w| w.to_lowercase())
+            .collect::<Vec<String>>();
+        let Some(first) = words.first() else {
+            return Vec::new();
+        };
+        let rest = words[1..]
+            .iter()
+            .map(|word| self.positions(word))
+            .collect::<Vec<&[usize]>>();
+        self.positions(first)
+            .iter()
+            .copied()
+            .filter(|start| {
+                rest.iter()
+                    .enumerate()
+                    .all(|(offset, list)| list.binary_search(&(start + offset + 1)).is_ok())
+            })
+            .collect()
+    }
+    /// Keyword in context lines for every occurrence of a word or phrase
+    pub fn kwic(&self, phrase: &str, width: usize) -> Vec<KwicLine> {
+        let length = phrase.unicode_words().count();
+        self.find_phrase(phrase)
+            .into_iter()
+            .map(|position| {
+                let start = self.spans[position].start;
+                let end = self.spans[position + length - 1].end;
+                let left = context(self.text[..start].chars().rev(), width);
+                KwicLine {
+                    position,
+                    left: left.chars().rev().collect(),
+                    keyword: context(self.text[start..end].chars(), end - start),
+                    right: context(self.text[end..].chars(), width),
+                    width,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Takes up to width characters, collapsing whitespace and dropping it at either end
+fn context<I: Iterator<Item = char>>(chars: I, width: usize) -> String {
+    let mut taken = String::new();
+    let mut count = 0;
+    let mut space = false;
+    for c in chars {
+        if count == width {
+            break;
+        }
+        if c.is_whitespace() {
+            space = count > 0;
+            continue;
+        }
+        if space {
+            if count + 1 == width {
+                break;
+            }
+            taken.push(' ');
+            count += 1;
+        }
+        space = false;
+        taken.push(c);
+        count += 1;
+    }
+    taken
+}
+
+/// Lines up keywords by right aligning the left context to the width
+impl fmt::Display for KwicLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>width$} [{}] {}",
+            self.left,
+            self.keyword,
+            self.right,
+            width = self.width
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::BOOK;
+
+    const TEXT: &str = "It is a truth universally acknowledged, that a single man\n\
+        in possession of a good fortune, must be in want of a wife. A single\n\
+        man, it is true.";
+
+    #[test]
+    fn positions_follow_word_order() {
+        let concordance = Concordance::new(TEXT.into());
+        assert_eq!(concordance.word_count(), 29);
+        assert_eq!(concordance.positions("single"), &[8, 24]);
+        assert_eq!(concordance.positions("IT"), &[0, 26]);
+        assert!(concordance.positions("darcy").is_empty());
+    }
+
+    #[test]
+    fn phrases_span_punctuation_and_lines() {
+        let concordance = Concordance::new(TEXT.into());
+        assert_eq!(concordance.find_phrase("single man"), vec![8, 24]);
+        assert_eq!(concordance.find_phrase("a single man"), vec![7, 23]);
+        assert_eq!(concordance.find_phrase("Man, in possession"), vec![9]);
+        assert!(concordance.find_phrase("man single").is_empty());
+        assert!(concordance.find_phrase("").is_empty());
+        assert!(concordance.find_phrase("...").is_empty());
+    }
+
+    #[test]
+    fn kwic_lines_have_context() {
+        let concordance = Concordance::new(TEXT.into());
+        let lines = concordance.kwic("single man", 12);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].left, "dged, that a");
+        assert_eq!(lines[0].keyword, "single man");
+        assert_eq!(lines[0].right, "in possessio");
+        assert_eq!(lines[1].keyword, "single man");
+        assert_eq!(lines[1].right, ", it is true");
+        assert_eq!(
+            lines[0].to_string(),
+            "dged, that a [single man] in possessio"
+        );
+        let lines = concordance.kwic("it", 5);
+        assert_eq!(lines[0].to_string(), "      [It] is a");
+        assert_eq!(lines[0].right, "is a");
+        assert_eq!(lines[1].left, "man,");
+    }
+
+    #[test]
+    fn kwic_over_the_book() {
+        let concordance = Concordance::new(BOOK.into());
+        let lines = concordance.kwic("Darcy", 30);
+        assert_eq!(lines.len(), concordance.positions("darcy").len());
+        assert!(lines.len() > 300);
+        for line in lines {
+            assert!(line.keyword.eq_ignore_ascii_case("darcy"));
+            assert!(line.left.chars().count() <= 30);
+            assert!(line.right.chars().count() <= 30);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use reqwest::StatusCode;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+
+#[derive(Debug)]
+pub enum StoryError {
+    Fetch(reqwest::Error),
+    Read(io::Error),
+    Status(StatusCode),
+    TooLarge(usize),
+    Decode(FromUtf8Error),
+    Unescape,
+    Empty,
+}
+
+impl fmt::Display for StoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoryError::Fetch(e) => write!(f, "Failed to fetch story: {}", e),
+            StoryError::Read(e) => write!(f, "Failed to read story: {}", e),
+            StoryError::Status(status) => write!(f, "Story request failed with {}", status),
+            StoryError::TooLarge(max_bytes) => {
+                write!(f, "Story is larger than {} bytes", max_bytes)
+            }
+            StoryError::Decode(e) => write!(f, "Story is not valid UTF-8: {}", e),
+            StoryError::Unescape => write!(f, "Story has a malformed escape sequence"),
+            StoryError::Empty => write!(f, "Story is empty"),
+        }
+    }
+}
+
+impl Error for StoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoryError::Fetch(e) => Some(e),
+            StoryError::Read(e) => Some(e),
+            StoryError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for StoryError {
+    fn from(e: reqwest::Error) -> StoryError {
+        StoryError::Fetch(e)
+    }
+}
+
+impl From<io::Error> for StoryError {
+    fn from(e: io::Error) -> StoryError {
+        StoryError::Read(e)
+    }
+}
+
+impl From<FromUtf8Error> for StoryError {
+    fn from(e: FromUtf8Error) -> StoryError {
+        StoryError::Decode(e)
+    }
+}
@@ -0,0 +1,555 @@
+use super::{Progress, ProgressSender, StoryError};
+use reqwest::header::{CONTENT_LENGTH, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, Response, StatusCode};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// The longest FetchClient waits between retries, however many there are
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Controls how FetchClient downloads stories. Retries wait for backoff,
+/// doubling after every attempt up to MAX_BACKOFF, and only happen for
+/// connection failures, timeouts and 5xx or 429 responses.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub timeout: Duration,
+    pub max_bytes: usize,
+    pub retries: u32,
+    pub backoff: Duration,
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> FetchOptions {
+        FetchOptions {
+            timeout: Duration::from_secs(30),
+            max_bytes: 16 * 1024 * 1024,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            cache_dir: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct FetchClient {
+    client: Client,
+    options: FetchOptions,
+}
+
+/// 64 bit FNV-1a, used to name cache files so the names stay the same
+/// across Rust releases
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// The validators saved next to a cached body
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CacheEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Vec<u8>,
+}
+
+impl FetchClient {
+    pub fn new(options: FetchOptions) -> Result<FetchClient, StoryError> {
+        let client = Client::builder().timeout(options.timeout).build()?;
+        Ok(FetchClient { client, options })
+    }
+    pub async fn fetch(&self, url: &str, progress: &ProgressSender) -> Result<Vec<u8>, StoryError> {
+        let cached = self.read_cache(url).await;
+        let mut attempt = 0;
+        loop {
+            match self.fetch_once(url, cached.as_ref(), progress).await {
+                Err(e) if attempt < self.options.retries && is_retryable(&e) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Ok(None) => return Ok(cached.map(|entry| entry.body).unwrap_or_default()),
+                Ok(Some(entry)) => {
+                    // The cache only saves a download next time, so failing to
+                    // write it must not lose the body we already have
+                    let _ = self.write_cache(url, &entry).await;
+                    return Ok(entry.body);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+        self.options.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+    /// Returns None when the server says the cached copy is still fresh
+    async fn fetch_once(
+        &self,
+        url: &str,
+        cached: Option<&CacheEntry>,
+        progress: &ProgressSender,
+    ) -> Result<Option<CacheEntry>, StoryError> {
+        let mut request = self.client.get(url);
+        if let Some(entry) = cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let resp = request.send().await?;
+        if resp.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(StoryError::Status(resp.status()));
+        }
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body = self.read_body(resp, progress).await?;
+        Ok(Some(CacheEntry {
+            etag,
+            last_modified,
+            body,
+        }))
+    }
+    async fn read_body(
+        &self,
+        mut resp: Response,
+        progress: &ProgressSender,
+    ) -> Result<Vec<u8>, StoryError> {
+        let max_bytes = self.options.max_bytes;
+        let length = resp
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        if length.is_some_and(|length| length > max_bytes) {
+            return Err(StoryError::TooLarge(max_bytes));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if body.len() + chunk.len() > max_bytes {
+                return Err(StoryError::TooLarge(max_bytes));
+            }
+            body.extend_from_slice(&chunk);
+            progress.send(Progress::Downloaded {
+                bytes: body.len(),
+                total: length,
+            });
+        }
+        Ok(body)
+    }
+    fn cache_path(&self, url: &str) -> Option<PathBuf> {
+        let hash = url.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        Some(
+            self.options
+                .cache_dir
+                .as_ref()?
+                .join(format!("{:016x}", hash)),
+        )
+    }
+    /// Ignores an entry saved for a different url whose name collided
+    async fn read_cache(&self, url: &str) -> Option<CacheEntry> {
+        let path = self.cache_path(url)?;
+        let body = tokio::fs::read(path.with_extension("body")).await.ok()?;
+        let meta = tokio::fs::read_to_string(path.with_extension("meta"))
+            .await
+            .ok()?;
+        let mut entry = CacheEntry {
+            body,
+            ..Default::default()
+        };
+        let mut saved_url = None;
+        for line in meta.lines() {
+            match line.split_once(": ") {
+                Some(("url", value)) => saved_url = Some(value),
+                Some(("etag", value)) => entry.etag = Some(value.to_string()),
+                Some(("last-modified", value)) => entry.last_modified = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        (saved_url == Some(url)).then_some(entry)
+    }
+    /// Both files are written to temporary names first. The old meta is removed
+    /// before the new body takes its place and the new meta goes in last, so a
+    /// failure part way leaves no entry rather than a body with old validators.
+    async fn write_cache(&self, url: &str, entry: &CacheEntry) -> Result<(), StoryError> {
+        let path = match self.cache_path(url) {
+            Some(path) if entry.etag.is_some() || entry.last_modified.is_some() => path,
+            _ => return Ok(()),
+        };
+        let mut meta = format!("url: {}\n", url);
+        if let Some(etag) = &entry.etag {
+            meta.push_str(&format!("etag: {}\n", etag));
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            meta.push_str(&format!("last-modified: {}\n", last_modified));
+        }
+        tokio::fs::create_dir_all(path.parent().unwrap_or(&path)).await?;
+        let body = write_temp(&path.with_extension("body"), &entry.body).await?;
+        let meta = match write_temp(&path.with_extension("meta"), meta.as_bytes()).await {
+            Ok(meta) => meta,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&body).await;
+                return Err(e);
+            }
+        };
+        let swapped = async {
+            match tokio::fs::remove_file(path.with_extension("meta")).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            tokio::fs::rename(&body, path.with_extension("body")).await?;
+            tokio::fs::rename(&meta, path.with_extension("meta")).await
+        };
+        if let Err(e) = swapped.await {
+            let _ = tokio::fs::remove_file(&body).await;
+            let _ = tokio::fs::remove_file(&meta).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
+
+/// Writes the bytes next to path under a temporary name and returns that name.
+/// The name is unique within the process as a batch may fetch a url twice at once.
+async fn write_temp(path: &Path, bytes: &[u8]) -> Result<PathBuf, StoryError> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    let next = NEXT.fetch_add(1, Ordering::SeqCst);
+    name.push(format!(".{}.{}.tmp", std::process::id(), next));
+    let temp = path.with_file_name(name);
+    let written = async {
+        let mut file = tokio::fs::File::create(&temp).await?;
+        file.write_all(bytes).await?;
+        file.sync_all().await
+    };
+    match written.await {
+        Ok(()) => Ok(temp),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            Err(e.into())
+        }
+    }
+}
+
+fn is_retryable(e: &StoryError) -> bool {
+    match e {
+        StoryError::Fetch(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+        StoryError::Status(status) => {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_server::{self, test_client};
+    use super::*;
+    use crate::test_support::temp_path;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn fetches_body() {
+        let url = test_server::serve("200 OK", b"Once upon a time").await;
+        assert_eq!(
+            test_client(0)
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"Once upon a time"
+        );
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let url = test_server::hang().await;
+        let result = test_client(0).fetch(&url, &ProgressSender::default()).await;
+        assert!(
+            matches!(&result, Err(StoryError::Fetch(e)) if e.is_timeout()),
+            "{:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let url = test_server::serve_with(|index, _| match index {
+            0 | 1 => test_server::response("503 Service Unavailable", &[], b""),
+            _ => test_server::response("200 OK", &[], b"third time lucky"),
+        })
+        .await;
+        assert_eq!(
+            test_client(2)
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"third time lucky"
+        );
+        let result = test_client(0).fetch(&url, &ProgressSender::default()).await;
+        assert_eq!(result.unwrap(), b"third time lucky");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_retries() {
+        let url = test_server::serve("503 Service Unavailable", b"").await;
+        let result = test_client(2).fetch(&url, &ProgressSender::default()).await;
+        assert!(
+            matches!(result, Err(StoryError::Status(status)) if status.as_u16() == 503),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        let client = test_client(40);
+        assert_eq!(client.backoff(0), Duration::from_millis(1));
+        assert_eq!(client.backoff(3), Duration::from_millis(8));
+        assert_eq!(client.backoff(16), MAX_BACKOFF);
+        assert_eq!(client.backoff(39), MAX_BACKOFF);
+        let client = FetchClient::new(FetchOptions {
+            backoff: Duration::MAX,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(client.backoff(0), MAX_BACKOFF);
+        assert_eq!(client.backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let requests = Arc::new(Mutex::new(0));
+        let seen = requests.clone();
+        let url = test_server::serve_with(move |_, _| {
+            *seen.lock().unwrap() += 1;
+            test_server::response("404 Not Found", &[], b"")
+        })
+        .await;
+        assert!(test_client(3)
+            .fetch(&url, &ProgressSender::default())
+            .await
+            .is_err());
+        assert_eq!(*requests.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_connection_failures() {
+        let url = test_server::closed().await;
+        let result = test_client(1).fetch(&url, &ProgressSender::default()).await;
+        assert!(matches!(result, Err(StoryError::Fetch(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn limits_body_size() {
+        let client = FetchClient::new(FetchOptions {
+            max_bytes: 10,
+            ..Default::default()
+        })
+        .unwrap();
+        let url = test_server::serve("200 OK", &[b'a'; 11]).await;
+        let result = client.fetch(&url, &ProgressSender::default()).await;
+        assert!(
+            matches!(result, Err(StoryError::TooLarge(10))),
+            "{:?}",
+            result
+        );
+        let url = test_server::serve_with(|_, _| {
+            let mut response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_vec();
+            response.extend_from_slice(&[b'a'; 11]);
+            response
+        })
+        .await;
+        let result = client.fetch(&url, &ProgressSender::default()).await;
+        assert!(
+            matches!(result, Err(StoryError::TooLarge(10))),
+            "{:?}",
+            result
+        );
+        let url = test_server::serve("200 OK", &[b'a'; 10]).await;
+        assert_eq!(
+            client
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_copy() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let url = test_server::serve_with(move |_, request| {
+            seen.lock().unwrap().push(request.to_lowercase());
+            if request.to_lowercase().contains("if-none-match: \"v1\"") {
+                test_server::response("304 Not Modified", &[], b"")
+            } else {
+                test_server::response(
+                    "200 OK",
+                    &[
+                        "ETag: \"v1\"",
+                        "Last-Modified: Tue, 26 Aug 2008 00:00:00 GMT",
+                    ],
+                    b"cached story",
+                )
+            }
+        })
+        .await;
+        let cache_dir = temp_path("etag");
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            client
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"cached story"
+        );
+        assert_eq!(
+            client
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"cached story"
+        );
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        assert!(requests[1].contains("if-modified-since: tue, 26 aug 2008 00:00:00 gmt"));
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn cache_names_are_stable() {
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(PathBuf::from("cache")),
+            ..Default::default()
+        })
+        .unwrap();
+        let path = client.cache_path("https://www.gutenberg.org/files/1342/1342-0.txt");
+        assert_eq!(path, Some(PathBuf::from("cache/28066052d9ce72e7")));
+        assert_eq!(test_client(0).cache_path("https://example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn ignores_cache_saved_for_another_url() {
+        let cache_dir = temp_path("collision");
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        let entry = CacheEntry {
+            etag: Some("\"v1\"".into()),
+            last_modified: None,
+            body: b"someone else's story".to_vec(),
+        };
+        client.write_cache("http://a/", &entry).await.unwrap();
+        assert_eq!(client.read_cache("http://a/").await, Some(entry));
+        let a = client.cache_path("http://a/").unwrap();
+        let b = client.cache_path("http://b/").unwrap();
+        std::fs::rename(a.with_extension("body"), b.with_extension("body")).unwrap();
+        std::fs::rename(a.with_extension("meta"), b.with_extension("meta")).unwrap();
+        assert_eq!(client.read_cache("http://b/").await, None);
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn still_returns_body_when_cache_cannot_be_written() {
+        let file = temp_path("not_a_dir");
+        std::fs::write(&file, b"").unwrap();
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(file.join("cache")),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = test_server::serve_with(|_, _| {
+            test_server::response("200 OK", &["ETag: \"v1\""], b"uncached story")
+        })
+        .await;
+        assert_eq!(
+            client
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"uncached story"
+        );
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rewriting_the_cache_replaces_body_and_validators_together() {
+        let cache_dir = temp_path("rewrite");
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        let entry = |etag: &str, body: &[u8]| CacheEntry {
+            etag: Some(etag.into()),
+            last_modified: None,
+            body: body.to_vec(),
+        };
+        client
+            .write_cache("http://a/", &entry("v1", b"old"))
+            .await
+            .unwrap();
+        client
+            .write_cache("http://a/", &entry("v2", b"new"))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.read_cache("http://a/").await,
+            Some(entry("v2", b"new"))
+        );
+        let meta = client
+            .cache_path("http://a/")
+            .unwrap()
+            .with_extension("meta");
+        std::fs::remove_file(&meta).unwrap();
+        std::fs::create_dir(&meta).unwrap();
+        assert!(client
+            .write_cache("http://a/", &entry("v3", b"newer"))
+            .await
+            .is_err());
+        assert_eq!(client.read_cache("http://a/").await, None);
+        let files = std::fs::read_dir(&cache_dir).unwrap().count();
+        assert_eq!(files, 2);
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_cache_without_validators() {
+        let cache_dir = temp_path("plain");
+        let client = FetchClient::new(FetchOptions {
+            cache_dir: Some(cache_dir.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        let url = test_server::serve("200 OK", b"fresh every time").await;
+        assert_eq!(
+            client
+                .fetch(&url, &ProgressSender::default())
+                .await
+                .unwrap(),
+            b"fresh every time"
+        );
+        assert!(!cache_dir.exists());
+    }
+}
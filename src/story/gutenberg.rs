@@ -0,0 +1,261 @@
+use crate::math::{letter_freq, word_freq};
+use hashbrown::HashMap;
+
+const BOM: char = '\u{feff}';
+
+/// The header fields Project Gutenberg puts before the start marker
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub release_date: Option<String>,
+    pub ebook_number: Option<u32>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    pub text: String,
+}
+
+/// A Project Gutenberg ebook with the license boilerplate removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub metadata: Metadata,
+    pub body: String,
+    pub chapters: Vec<Chapter>,
+}
+
+impl Chapter {
+    pub fn word_freq(&self) -> HashMap<String, u32> {
+        word_freq(self.text.as_str())
+    }
+    pub fn letter_freq(&self) -> HashMap<String, u32> {
+        letter_freq(self.text.as_str())
+    }
+}
+
+/// Splits an ebook into its metadata, body and chapters. Text without the
+/// START and END markers is treated as all body.
+pub fn parse(text: &str) -> Document {
+    let text = text.trim_start_matches(BOM);
+    let lines = text.lines().collect::<Vec<&str>>();
+    let start = lines.iter().position(|line| is_marker(line, "START"));
+    let header = &lines[..start.unwrap_or(0)];
+    let mut body = &lines[start.map_or(0, |i| i + 1)..];
+    if let Some(end) = body.iter().rposition(|line| is_marker(line, "END")) {
+        body = &body[..end];
+    }
+    while let Some((last, rest)) = body.split_last() {
+        let last = last.trim();
+        if last.is_empty() || last.starts_with("End of the Project Gutenberg") {
+            body = rest;
+        } else {
+            break;
+        }
+    }
+    Document {
+        metadata: parse_metadata(header),
+        body: body.join("\n").trim().to_string(),
+        chapters: split_chapters(body),
+    }
+}
+
+/// Matches lines like "*** START OF THE PROJECT GUTENBERG EBOOK ..." or
+/// "***END OF THIS PROJECT GUTENBERG EBOOK ..." where kind is START or END
+fn is_marker(line: &str, kind: &str) -> bool {
+    let line = line.trim().to_uppercase();
+    let Some(line) = line.strip_prefix("***") else {
+        return false;
+    };
+    let line = line.trim_start().strip_prefix(kind).unwrap_or_default();
+    ["OF THE PROJECT GUTENBERG", "OF THIS PROJECT GUTENBERG"]
+        .iter()
+        .any(|rest| line.trim_start().starts_with(rest))
+}
+
+fn parse_metadata(header: &[&str]) -> Metadata {
+    let mut metadata = Metadata::default();
+    for line in header {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim().to_string()),
+            None => continue,
+        };
+        match key {
+            "Title" => metadata.title = Some(value),
+            "Author" => metadata.author = Some(value),
+            "Language" => metadata.language = Some(value),
+            "Release Date" => {
+                let (date, number) = match value.split_once('[') {
+                    Some((date, number)) => (date.trim().to_string(), number),
+                    None => (value.clone(), ""),
+                };
+                metadata.ebook_number = number
+                    .trim_end_matches(']')
+                    .rsplit('#')
+                    .next()
+                    .and_then(|n| n.trim().parse().ok());
+                metadata.release_date = Some(date);
+            }
+            _ => {}
+        }
+    }
+    metadata
+}
+
+/// Starts a chapter at every heading line, dropping headings with no text
+/// after them such as those in a table of contents
+fn split_chapters(body: &[&str]) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in body {
+        if is_chapter_heading(line) {
+            chapters.extend(current.take().and_then(to_chapter));
+            current = Some((line.trim().to_string(), Vec::new()));
+        } else if let Some((_, text)) = current.as_mut() {
+            text.push(line);
+        }
+    }
+    chapters.extend(current.and_then(to_chapter));
+    chapters
+}
+
+fn to_chapter((title, text): (String, Vec<&str>)) -> Option<Chapter> {
+    let text = text
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    (!text.is_empty()).then_some(Chapter { title, text })
+}
+
+fn is_chapter_heading(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some(word), Some(number), None) => {
+            let number = number.trim_end_matches(['.', ':']);
+            word.eq_ignore_ascii_case("chapter")
+                && !number.is_empty()
+                && (number.chars().all(|c| c.is_ascii_digit())
+                    || number.chars().all(|c| "IVXLCDM".contains(c)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::BOOK;
+
+    #[test]
+    fn parses_metadata() {
+        let document = parse(BOOK);
+        assert_eq!(
+            document.metadata,
+            Metadata {
+                title: Some("Pride and Prejudice".into()),
+                author: Some("Jane Austen".into()),
+                release_date: Some("August 26, 2008".into()),
+                ebook_number: Some(1342),
+                language: Some("English".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn strips_bom_and_boilerplate() {
+        let document = parse(BOOK);
+        assert!(document
+            .body
+            .starts_with("Produced by Anonymous Volunteers"));
+        assert!(document.body.ends_with("uniting them."));
+        assert!(!document.body.contains('\u{feff}'));
+        assert!(!document.body.contains("PROJECT GUTENBERG"));
+        assert!(!document.body.contains("Project Gutenberg"));
+    }
+
+    #[test]
+    fn splits_chapters() {
+        let document = parse(BOOK);
+        assert_eq!(document.chapters.len(), 61);
+        for (index, chapter) in document.chapters.iter().enumerate() {
+            assert_eq!(chapter.title, format!("Chapter {}", index + 1));
+        }
+        let first = &document.chapters[0];
+        assert!(first
+            .text
+            .starts_with("It is a truth universally acknowledged"));
+        assert!(document.chapters[60].text.ends_with("uniting them."));
+    }
+
+    #[test]
+    fn chapter_frequencies() {
+        let document = parse(BOOK);
+        let first = document.chapters[0].word_freq();
+        assert_eq!(first.get("netherfield"), Some(&2));
+        let total = document
+            .chapters
+            .iter()
+            .map(|chapter| chapter.word_freq().values().sum::<u32>())
+            .sum::<u32>();
+        let body = word_freq(document.body.as_str()).values().sum::<u32>();
+        assert!(total < body && total > body * 9 / 10);
+        assert!(document.chapters[0].letter_freq().contains_key("“"));
+    }
+
+    #[test]
+    fn plain_text_is_all_body() {
+        let document = parse("\u{feff}CHAPTER I.\nCall me Ishmael.\n\nCHAPTER II\nThe Carpet-Bag.");
+        assert_eq!(document.metadata, Metadata::default());
+        assert!(document.body.starts_with("CHAPTER I."));
+        assert_eq!(
+            document.chapters,
+            vec![
+                Chapter {
+                    title: "CHAPTER I.".into(),
+                    text: "Call me Ishmael.".into()
+                },
+                Chapter {
+                    title: "CHAPTER II".into(),
+                    text: "The Carpet-Bag.".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn markers_match_the_whole_prefix() {
+        let start = "*** START OF THE PROJECT GUTENBERG EBOOK THE LEGEND OF SLEEPY HOLLOW ***";
+        assert!(is_marker(start, "START"));
+        assert!(!is_marker(start, "END"));
+        assert!(is_marker(
+            "***END OF THIS PROJECT GUTENBERG EBOOK***",
+            "END"
+        ));
+        assert!(!is_marker(
+            "*** THE END OF IT, SAID THE PROJECT GUTENBERG",
+            "END"
+        ));
+        let document = parse(&format!("Title: Sleepy Hollow\n{}\nIchabod Crane.", start));
+        assert_eq!(document.metadata.title.as_deref(), Some("Sleepy Hollow"));
+        assert_eq!(document.body, "Ichabod Crane.");
+        let document = parse(&format!(
+            "{}\nThe tale.\n*** END OF THE PROJECT GUTENBERG EBOOK ***\nLicense",
+            start
+        ));
+        assert_eq!(document.body, "The tale.");
+        assert_eq!(parse(start).body, "");
+    }
+
+    #[test]
+    fn headings_need_a_number() {
+        assert!(is_chapter_heading("      Chapter 12"));
+        assert!(is_chapter_heading("CHAPTER XLI."));
+        assert!(!is_chapter_heading("Chapter and verse"));
+        assert!(!is_chapter_heading("Chapter 1 begins"));
+    }
+}
@@ -0,0 +1,189 @@
+use hashbrown::HashMap;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Generates prose from the words that followed each run of `order` words in
+/// the training text. Words are split like word_freq but keep their case.
+/// States and followers are kept in a fixed order so a seeded RNG always
+/// produces the same text.
+pub struct MarkovChain {
+    order: usize,
+    states: Vec<Vec<String>>,
+    lookup: HashMap<Vec<String>, usize>,
+    followers: Vec<BTreeMap<String, u32>>,
+}
+
+impl MarkovChain {
+    /// An untrained chain, an order of 0 is treated as 1
+    pub fn new(order: usize) -> MarkovChain {
+        MarkovChain {
+            order: order.max(1),
+            states: Vec::new(),
+            lookup: HashMap::new(),
+            followers: Vec::new(),
+        }
+    }
+    pub fn train(&mut self, text: &str) {
+        let words = text.unicode_words().collect::<Vec<&str>>();
+        for window in words.windows(self.order + 1) {
+            let (state, next) = window.split_at(self.order);
+            let state = state.iter().map(|w| w.to_string()).collect::<Vec<String>>();
+            let index = match self.lookup.get(&state) {
+                Some(index) => *index,
+                None => {
+                    self.lookup.insert(state.clone(), self.states.len());
+                    self.states.push(state);
+                    self.followers.push(BTreeMap::new());
+                    self.states.len() - 1
+                }
+            };
+            let count = self.followers[index]
+                .entry(next[0].to_string())
+                .or_insert(0);
+            *count += 1;
+        }
+    }
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+    /// Generates `words` words starting from a random state and jumping to
+    /// another whenever the current one was never followed by anything.
+    /// Empty if untrained.
+    pub fn generate<R: Rng>(&self, rng: &mut R, words: usize) -> String {
+        let mut output = Vec::with_capacity(words);
+        if self.states.is_empty() {
+            return String::new();
+        }
+        let mut state = Vec::new();
+        while output.len() < words {
+            let next = self
+                .lookup
+                .get(&state)
+                .and_then(|&index| pick(&self.followers[index], rng));
+            match next {
+                Some(word) => {
+                    state.remove(0);
+                    state.push(word.to_string());
+                    output.push(word.to_string());
+                }
+                None => {
+                    state = self.states[rng.gen_range(0..self.states.len())].clone();
+                    output.extend(state.iter().take(words - output.len()).cloned());
+                }
+            }
+        }
+        output.join(" ")
+    }
+    /// Same as generate with a ChaCha8Rng seeded from seed. Unlike StdRng its
+    /// output is stable across rand releases, so a seed always gives this text.
+    pub fn generate_seeded(&self, seed: u64, words: usize) -> String {
+        self.generate(&mut ChaCha8Rng::seed_from_u64(seed), words)
+    }
+}
+
+/// Picks a follower with probability proportional to its count
+fn pick<'a, R: Rng>(followers: &'a BTreeMap<String, u32>, rng: &mut R) -> Option<&'a str> {
+    let total = followers.values().sum::<u32>();
+    if total == 0 {
+        return None;
+    }
+    let mut target = rng.gen_range(0..total);
+    for (word, count) in followers {
+        if target < *count {
+            return Some(word);
+        }
+        target -= count;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::BOOK;
+    use hashbrown::HashSet;
+
+    const TEXT: &str =
+        "the cat sat on the mat and the cat ran to the dog and the dog sat on the mat";
+
+    fn ngrams(text: &str, n: usize) -> HashSet<Vec<String>> {
+        let words = text
+            .unicode_words()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        words.windows(n).map(|w| w.to_vec()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_text() {
+        let mut chain = MarkovChain::new(2);
+        chain.train(TEXT);
+        let text = chain.generate_seeded(7, 30);
+        assert_eq!(text, chain.generate_seeded(7, 30));
+        // ChaCha8Rng promises the same stream for a seed, so this text is fixed
+        assert_eq!(
+            chain.generate_seeded(7, 12),
+            "sat on the mat and the dog and the cat ran to"
+        );
+        assert_eq!(text.split(' ').count(), 30);
+        let others = (0..10)
+            .map(|seed| chain.generate_seeded(seed, 30))
+            .collect::<HashSet<String>>();
+        assert!(others.len() > 1);
+    }
+
+    #[test]
+    fn generated_text_only_uses_seen_transitions() {
+        for order in 1..=3 {
+            let mut chain = MarkovChain::new(order);
+            chain.train(TEXT);
+            let seen = ngrams(TEXT, order + 1);
+            for seed in 0..20 {
+                let text = chain.generate_seeded(seed, 50);
+                for window in ngrams(&text, order + 1) {
+                    assert!(seen.contains(&window), "{:?} in {:?}", window, text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn follows_counts() {
+        let mut chain = MarkovChain::new(1);
+        chain.train("a b a b a b a c");
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut after_a = HashMap::new();
+        for _ in 0..2000 {
+            let followers = &chain.followers[chain.lookup[&vec!["a".to_string()]]];
+            let count = after_a.entry(pick(followers, &mut rng)).or_insert(0);
+            *count += 1;
+        }
+        assert_eq!(after_a.len(), 2);
+        assert!(after_a[&Some("b")] > 3 * after_a[&Some("c")]);
+    }
+
+    #[test]
+    fn handles_short_and_empty_training() {
+        let chain = MarkovChain::new(2);
+        assert_eq!(chain.generate_seeded(1, 10), "");
+        let mut chain = MarkovChain::new(0);
+        chain.train("one");
+        assert_eq!(chain.state_count(), 0);
+        chain.train("stop here");
+        assert_eq!(chain.state_count(), 1);
+        assert_eq!(chain.generate_seeded(3, 4), "stop here stop here");
+        assert_eq!(chain.generate_seeded(3, 1), "stop");
+    }
+
+    #[test]
+    fn trains_on_the_book() {
+        let mut chain = MarkovChain::new(2);
+        chain.train(BOOK);
+        assert!(chain.state_count() > 50_000);
+        let text = chain.generate_seeded(2024, 40);
+        assert_eq!(text, chain.generate_seeded(2024, 40));
+        assert_eq!(text.split(' ').count(), 40);
+    }
+}
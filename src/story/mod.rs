@@ -0,0 +1,249 @@
+mod batch;
+mod concordance;
+mod error;
+mod fetch;
+pub mod gutenberg;
+mod markov;
+mod progress;
+mod readability;
+mod report;
+mod search;
+mod source;
+#[cfg(test)]
+mod test_server;
+
+pub use batch::*;
+pub use concordance::*;
+pub use error::*;
+pub use fetch::*;
+pub use markov::*;
+pub use progress::*;
+pub use readability::*;
+pub use report::*;
+pub use search::*;
+pub use source::*;
+
+use hashbrown::HashMap;
+use serde::Serialize;
+use std::cmp;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use unescape::unescape;
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct StringCount(pub String, pub u32);
+
+/// Sorts by count descending, then by key so ties always come out the same way
+impl cmp::Ord for StringCount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.1.cmp(&self.1).then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl cmp::PartialOrd for StringCount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Finds the k entries with the highest counts, sorted by StringCount's ordering.
+/// Keeps a heap of the best k seen so far whose top is the worst of them.
+pub fn top_k(k: usize, map: &HashMap<String, u32>) -> Vec<StringCount> {
+    let mut top = BinaryHeap::with_capacity(k.min(map.len()) + 1);
+    for (key, count) in map.iter() {
+        if top.len() == k {
+            match top.peek() {
+                Some(StringCount(worst_key, worst_count))
+                    if (count, worst_key) > (worst_count, key) =>
+                {
+                    top.pop();
+                }
+                _ => continue,
+            }
+        }
+        top.push(StringCount(key.to_string(), *count));
+    }
+    top.into_sorted_vec()
+}
+
+/// Loads the source and then parses and analyzes it on a blocking thread,
+/// so a long book never holds up the runtime's workers
+pub async fn read_story(
+    source: StorySource,
+    client: &FetchClient,
+    progress: &ProgressSender,
+) -> Result<StoryReport, StoryError> {
+    let resp = source.load(client, progress).await?;
+    let progress = progress.clone();
+    tokio::task::spawn_blocking(move || {
+        let story = unescape(&resp).ok_or(StoryError::Unescape)?;
+        progress.send(Progress::Parsing);
+        let story = gutenberg::parse(&story).body;
+        if story.is_empty() {
+            return Err(StoryError::Empty);
+        }
+        progress.send(Progress::Analyzing);
+        Ok(StoryReport::new(story))
+    })
+    .await
+    .expect("Story analysis panicked!")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::word_freq;
+    use crate::test_support::{counts, BOOK};
+    use test_server::test_client;
+
+    async fn read(source: StorySource) -> Result<StoryReport, StoryError> {
+        read_story(source, &test_client(0), &ProgressSender::default()).await
+    }
+
+    fn keys(top: Vec<StringCount>) -> Vec<String> {
+        top.into_iter().map(|entry| entry.0).collect()
+    }
+
+    #[test]
+    fn top_k_is_sorted_by_count() {
+        let map = counts(&[("a", 1), ("b", 5), ("c", 3), ("d", 4), ("e", 2)]);
+        let top = top_k(3, &map);
+        assert_eq!(
+            top,
+            vec![
+                StringCount("b".into(), 5),
+                StringCount("d".into(), 4),
+                StringCount("c".into(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_k_breaks_ties_by_key() {
+        let map = counts(&[("d", 2), ("b", 2), ("a", 1), ("c", 2), ("e", 3)]);
+        assert_eq!(keys(top_k(3, &map)), vec!["e", "b", "c"]);
+        assert_eq!(keys(top_k(5, &map)), vec!["e", "b", "c", "d", "a"]);
+    }
+
+    #[test]
+    fn top_k_handles_edge_sizes() {
+        let map = counts(&[("a", 1), ("b", 2)]);
+        assert!(top_k(0, &map).is_empty());
+        assert_eq!(keys(top_k(10, &map)), vec!["b", "a"]);
+        assert!(top_k(3, &HashMap::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_story_runs_offline() {
+        let story = read(StorySource::Text("The cat saw the dog.".into()))
+            .await
+            .unwrap();
+        assert_eq!(story.text, "The cat saw the dog.");
+        assert_eq!(story.top_words[0], StringCount("the".into(), 2));
+        let book = read("data/book.txt".into()).await.unwrap();
+        assert!(book.text.starts_with("Produced by Anonymous Volunteers"));
+        assert!(!book.text.contains("PROJECT GUTENBERG"));
+    }
+
+    #[tokio::test]
+    async fn read_story_fetches_over_http() {
+        let url = test_server::serve("200 OK", b"The cat saw the dog.").await;
+        let story = read(url.as_str().into()).await.unwrap();
+        assert_eq!(story.text, "The cat saw the dog.");
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_progress() {
+        let url = test_server::serve("200 OK", b"The cat saw the dog.").await;
+        let client = test_client(0);
+        let (progress, mut updates) = ProgressSender::channel();
+        read_story(url.as_str().into(), &client, &progress)
+            .await
+            .unwrap();
+        drop(progress);
+        let mut seen = Vec::new();
+        while let Some(update) = updates.recv().await {
+            seen.push(update);
+        }
+        let stages = seen.split_off(seen.len() - 3);
+        assert_eq!(
+            stages,
+            vec![Progress::Decoding, Progress::Parsing, Progress::Analyzing]
+        );
+        assert_eq!(
+            seen.last(),
+            Some(&Progress::Downloaded {
+                bytes: 20,
+                total: Some(20)
+            })
+        );
+        let (progress, mut updates) = ProgressSender::channel();
+        read_story("data/book.txt".into(), &client, &progress)
+            .await
+            .unwrap();
+        drop(progress);
+        let mut downloaded = 0;
+        while let Some(update) = updates.recv().await {
+            if let Progress::Downloaded { bytes, .. } = update {
+                assert!(bytes > downloaded);
+                downloaded = bytes;
+            }
+        }
+        assert_eq!(downloaded, BOOK.len());
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_fetch_failure() {
+        let url = test_server::closed().await;
+        let result = read(url.as_str().into()).await;
+        assert!(matches!(result, Err(StoryError::Fetch(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_bad_status() {
+        let url = test_server::serve("404 Not Found", b"Not Found").await;
+        let result = read(url.as_str().into()).await;
+        assert!(
+            matches!(result, Err(StoryError::Status(status)) if status.as_u16() == 404),
+            "{:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_decoding_failure() {
+        let url = test_server::serve("200 OK", b"caf\xe9").await;
+        let result = read(url.as_str().into()).await;
+        assert!(matches!(result, Err(StoryError::Decode(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_unescape_failure() {
+        let url = test_server::serve("200 OK", b"bad \\q escape").await;
+        let result = read(url.as_str().into()).await;
+        assert!(matches!(result, Err(StoryError::Unescape)), "{:?}", result);
+        let result = read(StorySource::Text("bad \\u12".into())).await;
+        assert!(matches!(result, Err(StoryError::Unescape)), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn read_story_reports_empty_document() {
+        let url = test_server::serve("200 OK", b"").await;
+        let result = read(url.as_str().into()).await;
+        assert!(matches!(result, Err(StoryError::Empty)), "{:?}", result);
+        let result = read(StorySource::Text(" \r\n ".into())).await;
+        assert!(matches!(result, Err(StoryError::Empty)), "{:?}", result);
+    }
+
+    #[test]
+    fn top_k_matches_full_sort() {
+        let map = word_freq(BOOK);
+        let mut expected = map
+            .iter()
+            .map(|(k, v)| StringCount(k.to_string(), *v))
+            .collect::<Vec<StringCount>>();
+        expected.sort();
+        expected.truncate(25);
+        assert_eq!(top_k(25, &map), expected);
+    }
+}
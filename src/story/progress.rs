@@ -0,0 +1,51 @@
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// What read_story is doing, downloads report every chunk received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    Downloaded { bytes: usize, total: Option<usize> },
+    Decoding,
+    Parsing,
+    Analyzing,
+}
+
+/// Sends progress updates if anyone is listening, the default sends nowhere
+#[derive(Debug, Clone, Default)]
+pub struct ProgressSender(Option<UnboundedSender<Progress>>);
+
+impl ProgressSender {
+    pub fn channel() -> (ProgressSender, UnboundedReceiver<Progress>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (ProgressSender(Some(tx)), rx)
+    }
+    /// Ignores a closed receiver so dropping it never fails the story
+    pub fn send(&self, progress: Progress) {
+        if let Some(tx) = &self.0 {
+            let _ = tx.send(progress);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn sends_to_receiver() {
+        let (progress, mut updates) = ProgressSender::channel();
+        progress.send(Progress::Parsing);
+        progress.clone().send(Progress::Analyzing);
+        drop(progress);
+        assert_eq!(updates.recv().await, Some(Progress::Parsing));
+        assert_eq!(updates.recv().await, Some(Progress::Analyzing));
+        assert_eq!(updates.recv().await, None);
+    }
+
+    #[test]
+    fn ignores_missing_or_closed_receiver() {
+        ProgressSender::default().send(Progress::Parsing);
+        let (progress, updates) = ProgressSender::channel();
+        drop(updates);
+        progress.send(Progress::Parsing);
+    }
+}
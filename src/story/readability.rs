@@ -0,0 +1,226 @@
+use hashbrown::HashSet;
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words with at least this many syllables count as complex for the Gunning fog index
+const COMPLEX_SYLLABLES: usize = 3;
+
+/// Readability scores along with the counts they are derived from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readability {
+    pub sentences: usize,
+    pub words: usize,
+    pub syllables: usize,
+    pub complex_words: usize,
+    pub unique_words: usize,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub gunning_fog: f64,
+    pub type_token_ratio: f64,
+}
+
+impl Readability {
+    pub fn new(text: &str) -> Readability {
+        let sentences = unwrap_lines(text)
+            .iter()
+            .map(|paragraph| paragraph.unicode_sentences().count())
+            .sum::<usize>();
+        let mut words = 0;
+        let mut syllable_count = 0;
+        let mut complex_words = 0;
+        let mut unique = HashSet::new();
+        for w in text.unicode_words() {
+            let n = syllables(w);
+            words += 1;
+            syllable_count += n;
+            if n >= COMPLEX_SYLLABLES {
+                complex_words += 1;
+            }
+            unique.insert(w.to_lowercase());
+        }
+        let words_per_sentence = words as f64 / sentences.max(1) as f64;
+        let syllables_per_word = syllable_count as f64 / words.max(1) as f64;
+        let complex_ratio = complex_words as f64 / words.max(1) as f64;
+        Readability {
+            sentences,
+            words,
+            syllables: syllable_count,
+            complex_words,
+            unique_words: unique.len(),
+            flesch_reading_ease: 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            flesch_kincaid_grade: 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            gunning_fog: 0.4 * (words_per_sentence + 100.0 * complex_ratio),
+            type_token_ratio: unique.len() as f64 / words.max(1) as f64,
+        }
+    }
+}
+
+/// Joins hard wrapped lines back into paragraphs, since a line break
+/// always ends a sentence under Unicode's segmentation rules
+fn unwrap_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .collect::<Vec<&str>>()
+        .split(|line| line.is_empty())
+        .filter(|lines| !lines.is_empty())
+        .map(|lines| lines.join(" "))
+        .collect()
+}
+
+/// Estimates syllables by counting groups of vowels, ignoring a silent final e
+pub fn syllables(word: &str) -> usize {
+    let letters = word
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<char>>();
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in &letters {
+        let vowel = is_vowel(*c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if let [.., before, consonant, 'e'] = letters[..] {
+        let ends_in_le = consonant == 'l' && !is_vowel(before);
+        if !is_vowel(consonant) && !ends_in_le && count > 1 {
+            count -= 1;
+        }
+    }
+    count.max(1)
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyàáâäæèéêëìíîïòóôöœùúûü".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story::gutenberg;
+    use crate::test_support::BOOK;
+    use float_cmp::approx_eq;
+
+    #[test]
+    fn estimates_syllables() {
+        let expected = [
+            ("the", 1),
+            ("cat", 1),
+            ("make", 1),
+            ("table", 2),
+            ("little", 2),
+            ("beautiful", 3),
+            ("Netherfield", 3),
+            ("readability", 5),
+            ("café", 2),
+            ("1342", 1),
+        ];
+        for (word, count) in expected {
+            assert_eq!(syllables(word), count, "word = {}", word);
+        }
+    }
+
+    #[test]
+    fn scores_simple_sentence() {
+        // Wikipedia's article on the Flesch-Kincaid readability tests gives this
+        // sentence as an example of a reading ease above 100, scoring 116
+        let readability = Readability::new("The cat sat on the mat.");
+        assert!(approx_eq!(
+            f64,
+            readability.flesch_reading_ease,
+            116.0,
+            epsilon = 0.5
+        ));
+        assert_eq!(readability.sentences, 1);
+        assert_eq!(readability.words, 6);
+        assert_eq!(readability.syllables, 6);
+        assert_eq!(readability.complex_words, 0);
+        assert_eq!(readability.unique_words, 5);
+        assert!(approx_eq!(
+            f64,
+            readability.flesch_reading_ease,
+            116.145,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.flesch_kincaid_grade,
+            -1.45,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.gunning_fog,
+            2.4,
+            epsilon = 1e-9
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.type_token_ratio,
+            5.0 / 6.0,
+            epsilon = 1e-9
+        ));
+    }
+
+    #[test]
+    fn scores_complex_sentences() {
+        let readability = Readability::new("Readability is beautiful. It is.");
+        assert_eq!(readability.sentences, 2);
+        assert_eq!(readability.words, 5);
+        assert_eq!(readability.syllables, 11);
+        assert_eq!(readability.complex_words, 2);
+        assert!(approx_eq!(
+            f64,
+            readability.gunning_fog,
+            0.4 * (2.5 + 40.0),
+            epsilon = 1e-9
+        ));
+    }
+
+    #[test]
+    fn empty_text_scores_zero_words() {
+        let readability = Readability::new("");
+        assert_eq!(readability.words, 0);
+        assert_eq!(readability.type_token_ratio, 0.0);
+        assert!(readability.flesch_reading_ease.is_finite());
+    }
+
+    /// Pins this implementation's output for the book so changes to sentence
+    /// splitting or syllable counting show up. These are not published scores.
+    #[test]
+    fn book_scores_do_not_drift() {
+        let document = gutenberg::parse(BOOK);
+        let readability = Readability::new(&document.body);
+        assert_eq!(readability.sentences, 7312);
+        assert_eq!(readability.words, 122350);
+        assert_eq!(readability.syllables, 179896);
+        assert_eq!(readability.complex_words, 15082);
+        assert_eq!(readability.unique_words, 6560);
+        assert!(approx_eq!(
+            f64,
+            readability.flesch_reading_ease,
+            65.4605,
+            epsilon = 1e-4
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.flesch_kincaid_grade,
+            8.2858,
+            epsilon = 1e-4
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.gunning_fog,
+            11.6239,
+            epsilon = 1e-4
+        ));
+        assert!(approx_eq!(
+            f64,
+            readability.type_token_ratio,
+            0.0536,
+            epsilon = 1e-4
+        ));
+    }
+}
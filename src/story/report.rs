@@ -0,0 +1,187 @@
+use super::{top_k, Readability, StringCount};
+use crate::math::{letter_freq, word_freq};
+use hashbrown::HashMap;
+use serde::Serialize;
+use std::fmt;
+
+const TOP_ENTRIES: usize = 5;
+const WORDS_PER_MINUTE: f64 = 200.0;
+
+/// The analysis read_story produces for a story. The full word and letter
+/// counts are kept for callers like read_stories but left out of the output.
+#[derive(Debug, Serialize)]
+pub struct StoryReport {
+    pub text: String,
+    #[serde(skip)]
+    pub word_freq: HashMap<String, u32>,
+    #[serde(skip)]
+    pub letter_freq: HashMap<String, u32>,
+    pub word_count: u32,
+    pub unique_words: usize,
+    pub top_words: Vec<StringCount>,
+    pub top_letters: Vec<StringCount>,
+    pub sentence_count: usize,
+    pub reading_time_minutes: f64,
+    pub readability: Readability,
+}
+
+impl StoryReport {
+    pub fn new(text: String) -> StoryReport {
+        let words = word_freq(text.as_str());
+        let letters = letter_freq(text.as_str());
+        let word_count = words.values().sum::<u32>();
+        let readability = Readability::new(&text);
+        StoryReport {
+            word_count,
+            unique_words: words.len(),
+            top_words: top_k(TOP_ENTRIES, &words),
+            top_letters: top_k(TOP_ENTRIES, &letters),
+            sentence_count: readability.sentences,
+            reading_time_minutes: word_count as f64 / WORDS_PER_MINUTE,
+            readability,
+            text,
+            word_freq: words,
+            letter_freq: letters,
+        }
+    }
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+    /// Writes every statistic as a category,key,value row, leaving out the text
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec![
+            "category,key,value".to_string(),
+            format!("summary,word_count,{}", self.word_count),
+            format!("summary,unique_words,{}", self.unique_words),
+            format!("summary,sentence_count,{}", self.sentence_count),
+            format!(
+                "summary,reading_time_minutes,{:.2}",
+                self.reading_time_minutes
+            ),
+            format!(
+                "readability,flesch_reading_ease,{:.2}",
+                self.readability.flesch_reading_ease
+            ),
+            format!(
+                "readability,flesch_kincaid_grade,{:.2}",
+                self.readability.flesch_kincaid_grade
+            ),
+            format!(
+                "readability,gunning_fog,{:.2}",
+                self.readability.gunning_fog
+            ),
+            format!(
+                "readability,type_token_ratio,{:.4}",
+                self.readability.type_token_ratio
+            ),
+        ];
+        for (category, entries) in [("word", &self.top_words), ("letter", &self.top_letters)] {
+            for StringCount(key, count) in entries {
+                rows.push(format!("{},{},{}", category, csv_field(key), count));
+            }
+        }
+        rows.join("\n") + "\n"
+    }
+}
+
+impl fmt::Display for StoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}\n", self.text)?;
+        writeln!(
+            f,
+            "{} words ({} unique) in {} sentences, about {:.0} minutes to read.",
+            self.word_count, self.unique_words, self.sentence_count, self.reading_time_minutes
+        )?;
+        writeln!(
+            f,
+            "Flesch reading ease {:.1}, Flesch-Kincaid grade {:.1}, Gunning fog {:.1}, type-token ratio {:.3}.",
+            self.readability.flesch_reading_ease,
+            self.readability.flesch_kincaid_grade,
+            self.readability.gunning_fog,
+            self.readability.type_token_ratio
+        )?;
+        for (label, entries) in [
+            ("Top words", &self.top_words),
+            ("Top letters", &self.top_letters),
+        ] {
+            writeln!(f, "{}:", label)?;
+            for StringCount(key, count) in entries {
+                writeln!(f, "  {:?} {}", key, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> StoryReport {
+        StoryReport::new("The cat sat. The cat ran, and the dog ran!".to_string())
+    }
+
+    #[test]
+    fn counts_story_statistics() {
+        let report = report();
+        assert_eq!(report.word_count, 10);
+        assert_eq!(report.unique_words, 6);
+        assert_eq!(report.sentence_count, 2);
+        assert_eq!(report.top_words[0], StringCount("the".into(), 3));
+        assert_eq!(report.top_words[1], StringCount("cat".into(), 2));
+        assert_eq!(report.top_letters[0], StringCount(" ".into(), 9));
+        assert!((report.reading_time_minutes - 0.05).abs() < 1e-9);
+        assert_eq!(report.word_freq, word_freq(report.text.as_str()));
+        assert_eq!(report.letter_freq, letter_freq(report.text.as_str()));
+    }
+
+    #[test]
+    fn renders_json() {
+        let json = report().to_json().unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["word_count"], 10);
+        assert_eq!(value["top_words"][0], serde_json::json!(["the", 3]));
+        assert_eq!(value["readability"]["sentences"], 2);
+        assert_eq!(value["text"], "The cat sat. The cat ran, and the dog ran!");
+        assert!(value.get("word_freq").is_none());
+    }
+
+    #[test]
+    fn renders_csv() {
+        let csv = StoryReport::new("a, \"a\"".to_string()).to_csv();
+        assert_eq!(
+            csv,
+            "category,key,value\n\
+             summary,word_count,2\n\
+             summary,unique_words,1\n\
+             summary,sentence_count,1\n\
+             summary,reading_time_minutes,0.01\n\
+             readability,flesch_reading_ease,120.21\n\
+             readability,flesch_kincaid_grade,-3.01\n\
+             readability,gunning_fog,0.80\n\
+             readability,type_token_ratio,0.5000\n\
+             word,a,2\n\
+             letter,\"\"\"\",2\n\
+             letter,a,2\n\
+             letter, ,1\n\
+             letter,\",\",1\n"
+        );
+    }
+
+    #[test]
+    fn renders_display() {
+        let text = report().to_string();
+        assert!(text.starts_with("The cat sat. The cat ran, and the dog ran!\n\n"));
+        assert!(text.contains("10 words (6 unique) in 2 sentences"));
+        assert!(text.contains("Flesch reading ease "));
+        assert!(text.contains("Top words:\n  \"the\" 3\n  \"cat\" 2\n"));
+    }
+}
@@ -0,0 +1,447 @@
+use hashbrown::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// BM25 term frequency saturation and document length normalization
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// A parsed search. Words are split and lowercased like word_freq.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Term(String),
+    Phrase(Vec<String>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub doc: usize,
+    pub name: String,
+    pub score: f64,
+}
+
+/// Word positions for every document a word appears in, ordered by document
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    names: Vec<String>,
+    lengths: Vec<usize>,
+    total_length: usize,
+    postings: HashMap<String, Vec<Posting>>,
+}
+
+#[derive(Debug)]
+struct Posting {
+    doc: usize,
+    positions: Vec<usize>,
+}
+
+impl SearchIndex {
+    pub fn new() -> SearchIndex {
+        Default::default()
+    }
+    /// Indexes a document and returns its number
+    pub fn add<S: Into<String>>(&mut self, name: S, text: &str) -> usize {
+        let doc = self.names.len();
+        let mut length = 0;
+        for (position, word) in text.unicode_words().enumerate() {
+            let postings = self.postings.entry(word.to_lowercase()).or_default();
+            match postings.last_mut() {
+                Some(posting) if posting.doc == doc => posting.positions.push(position),
+                _ => postings.push(Posting {
+                    doc,
+                    positions: vec![position],
+                }),
+            }
+            length = position + 1;
+        }
+        self.names.push(name.into());
+        self.lengths.push(length);
+        self.total_length += length;
+        doc
+    }
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+    /// Parses the query and ranks the matching documents, None if it is malformed
+    pub fn search(&self, query: &str) -> Option<Vec<SearchHit>> {
+        Some(self.search_query(&Query::parse(query)?))
+    }
+    /// Ranks documents matching the query by the BM25 score of the terms and
+    /// phrases it asks for, best first. Terms under NOT do not add to a score.
+    pub fn search_query(&self, query: &Query) -> Vec<SearchHit> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut scored = Vec::new();
+        query.scored_terms(&mut scored);
+        let counts = scored
+            .iter()
+            .map(|query| self.counts(query))
+            .collect::<Vec<HashMap<usize, usize>>>();
+        let mut hits = self
+            .matches(query)
+            .into_iter()
+            .map(|doc| SearchHit {
+                doc,
+                name: self.names[doc].to_string(),
+                score: counts.iter().map(|counts| self.bm25(doc, counts)).sum(),
+            })
+            .collect::<Vec<SearchHit>>();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.doc.cmp(&b.doc)));
+        hits
+    }
+    /// Documents matching the query, in order
+    pub fn matches(&self, query: &Query) -> Vec<usize> {
+        match query {
+            Query::Term(_) | Query::Phrase(_) => {
+                let mut docs = self.counts(query).into_keys().collect::<Vec<usize>>();
+                docs.sort_unstable();
+                docs
+            }
+            Query::And(queries) => {
+                let mut sets = queries.iter().map(|query| self.matches(query));
+                let first = sets.next().unwrap_or_default();
+                sets.fold(first, |docs, other| {
+                    docs.into_iter()
+                        .filter(|doc| other.binary_search(doc).is_ok())
+                        .collect()
+                })
+            }
+            Query::Or(queries) => {
+                let mut docs = queries
+                    .iter()
+                    .flat_map(|query| self.matches(query))
+                    .collect::<Vec<usize>>();
+                docs.sort_unstable();
+                docs.dedup();
+                docs
+            }
+            Query::Not(query) => {
+                let excluded = self.matches(query);
+                (0..self.len())
+                    .filter(|doc| excluded.binary_search(doc).is_err())
+                    .collect()
+            }
+        }
+    }
+    /// How often a term or phrase occurs in each document it occurs in
+    fn counts(&self, query: &Query) -> HashMap<usize, usize> {
+        let words = match query {
+            Query::Term(word) => std::slice::from_ref(word),
+            Query::Phrase(words) => words.as_slice(),
+            _ => return HashMap::new(),
+        };
+        let Some((first, rest)) = words.split_first() else {
+            return HashMap::new();
+        };
+        let rest = rest
+            .iter()
+            .map(|word| self.postings.get(word).map_or(&[][..], |p| p.as_slice()))
+            .collect::<Vec<&[Posting]>>();
+        let mut counts = HashMap::new();
+        for posting in self.postings.get(first).into_iter().flatten() {
+            let following = rest
+                .iter()
+                .map(|postings| {
+                    let index = postings.binary_search_by_key(&posting.doc, |p| p.doc);
+                    index.ok().map(|index| &postings[index].positions)
+                })
+                .collect::<Option<Vec<&Vec<usize>>>>();
+            let Some(following) = following else {
+                continue;
+            };
+            let count = posting
+                .positions
+                .iter()
+                .filter(|start| {
+                    following.iter().enumerate().all(|(offset, positions)| {
+                        positions.binary_search(&(*start + offset + 1)).is_ok()
+                    })
+                })
+                .count();
+            if count > 0 {
+                counts.insert(posting.doc, count);
+            }
+        }
+        counts
+    }
+    fn bm25(&self, doc: usize, counts: &HashMap<usize, usize>) -> f64 {
+        let Some(&tf) = counts.get(&doc) else {
+            return 0.0;
+        };
+        let docs = self.len() as f64;
+        let df = counts.len() as f64;
+        let idf = ((docs - df + 0.5) / (df + 0.5) + 1.0).ln();
+        let average_length = self.total_length as f64 / docs;
+        let length = self.lengths[doc] as f64 / average_length;
+        let tf = tf as f64;
+        idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * length))
+    }
+}
+
+impl Query {
+    /// Parses words, "quoted phrases" and parentheses joined by AND, OR and
+    /// NOT. Words next to each other without an operator must all match.
+    pub fn parse(query: &str) -> Option<Query> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let query = parser.or()?;
+        (parser.next == parser.tokens.len()).then_some(query)
+    }
+    fn scored_terms<'a>(&'a self, terms: &mut Vec<&'a Query>) {
+        match self {
+            Query::Term(_) | Query::Phrase(_) => terms.push(self),
+            Query::And(queries) | Query::Or(queries) => {
+                for query in queries {
+                    query.scored_terms(terms);
+                }
+            }
+            Query::Not(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Words(Vec<String>),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(query: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '"' => {
+                let end = chars.find(|(_, c)| *c == '"')?.0;
+                Token::Words(words(&query[start + 1..end]))
+            }
+            _ => {
+                let mut end = query.len();
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        end = index;
+                        break;
+                    }
+                    chars.next();
+                }
+                match &query[start..end] {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    word => Token::Words(words(word)),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    Some(tokens)
+}
+
+fn words(s: &str) -> Vec<String> {
+    s.unicode_words().map(|w| w.to_lowercase()).collect()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+    fn take(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+    fn or(&mut self) -> Option<Query> {
+        let mut queries = vec![self.and()?];
+        while self.take(&Token::Or) {
+            queries.push(self.and()?);
+        }
+        Some(join(queries, Query::Or))
+    }
+    fn and(&mut self) -> Option<Query> {
+        let mut queries = vec![self.not()?];
+        loop {
+            if self.take(&Token::And) {
+                queries.push(self.not()?);
+            } else if matches!(self.peek(), None | Some(Token::Or | Token::Close)) {
+                return Some(join(queries, Query::And));
+            } else {
+                queries.push(self.not()?);
+            }
+        }
+    }
+    fn not(&mut self) -> Option<Query> {
+        if self.take(&Token::Not) {
+            return Some(Query::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+    fn atom(&mut self) -> Option<Query> {
+        let token = self.peek()?.clone();
+        self.next += 1;
+        match token {
+            Token::Open => {
+                let query = self.or()?;
+                self.take(&Token::Close).then_some(query)
+            }
+            Token::Words(mut words) => match words.len() {
+                0 => None,
+                1 => words.pop().map(Query::Term),
+                _ => Some(Query::Phrase(words)),
+            },
+            _ => None,
+        }
+    }
+}
+
+fn join(mut queries: Vec<Query>, combine: fn(Vec<Query>) -> Query) -> Query {
+    if queries.len() == 1 {
+        queries.remove(0)
+    } else {
+        combine(queries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::BOOK;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.add("darcy", "Mr. Darcy of Pemberley was a proud man. Darcy!");
+        index.add("wickham", "Mr. Wickham spoke of Pemberley and of Darcy.");
+        index.add(
+            "bennet",
+            "Mr. Bennet was a sensible man; a single man he was not.",
+        );
+        index.add("empty", "");
+        index
+    }
+
+    fn names(hits: Vec<SearchHit>) -> Vec<String> {
+        hits.into_iter().map(|hit| hit.name).collect()
+    }
+
+    fn term(word: &str) -> Query {
+        Query::Term(word.into())
+    }
+
+    #[test]
+    fn parses_queries() {
+        assert_eq!(Query::parse("Darcy"), Some(term("darcy")));
+        assert_eq!(
+            Query::parse("darcy pemberley OR NOT \"Single man\""),
+            Some(Query::Or(vec![
+                Query::And(vec![term("darcy"), term("pemberley")]),
+                Query::Not(Box::new(Query::Phrase(vec!["single".into(), "man".into()]))),
+            ]))
+        );
+        assert_eq!(
+            Query::parse("(a OR b) AND c"),
+            Some(Query::And(vec![
+                Query::Or(vec![term("a"), term("b")]),
+                term("c")
+            ]))
+        );
+        assert_eq!(Query::parse("mr.darcy"), Some(term("mr.darcy")));
+        for bad in [
+            "",
+            "darcy AND",
+            "(darcy",
+            "darcy)",
+            "\"darcy",
+            "NOT",
+            "!!",
+            "OR x",
+        ] {
+            assert_eq!(Query::parse(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn boolean_queries_match_documents() {
+        let index = index();
+        assert_eq!(index.len(), 4);
+        let matches = |query: &str| index.matches(&Query::parse(query).unwrap());
+        assert_eq!(matches("darcy"), vec![0, 1]);
+        assert_eq!(matches("darcy AND proud"), vec![0]);
+        assert_eq!(matches("darcy pemberley"), vec![0, 1]);
+        assert_eq!(matches("proud OR sensible"), vec![0, 2]);
+        assert_eq!(matches("NOT darcy"), vec![2, 3]);
+        assert_eq!(matches("mr NOT (darcy OR bennet)"), Vec::<usize>::new());
+        assert_eq!(matches("mr NOT darcy"), vec![2]);
+        assert_eq!(matches("elizabeth"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn phrases_need_adjacent_words() {
+        let index = index();
+        let matches = |query: &str| index.matches(&Query::parse(query).unwrap());
+        assert_eq!(matches("\"single man\""), vec![2]);
+        assert_eq!(matches("\"of pemberley\""), vec![0, 1]);
+        assert_eq!(matches("\"pemberley of\""), Vec::<usize>::new());
+        assert_eq!(matches("\"proud man darcy\""), vec![0]);
+        assert_eq!(matches("\"man he was not\""), vec![2]);
+    }
+
+    #[test]
+    fn ranks_with_bm25() {
+        let index = index();
+        assert_eq!(
+            names(index.search("darcy").unwrap()),
+            vec!["darcy", "wickham"]
+        );
+        let hits = index.search("man").unwrap();
+        assert_eq!(names(hits.clone()), vec!["bennet", "darcy"]);
+        assert!(hits[0].score > hits[1].score);
+        let hits = index.search("NOT darcy").unwrap();
+        assert!(hits.iter().all(|hit| hit.score == 0.0));
+        assert_eq!(index.search("darcy AND"), None);
+        let mut single = SearchIndex::new();
+        single.add("only", "a cat");
+        let hits = single.search("cat").unwrap();
+        assert!(hits[0].score > 0.0);
+        assert!(SearchIndex::new().search("cat").unwrap().is_empty());
+    }
+
+    #[test]
+    fn searches_the_book_by_chapter() {
+        let chapters = super::super::gutenberg::parse(BOOK).chapters;
+        let mut index = SearchIndex::new();
+        for chapter in &chapters {
+            index.add(chapter.title.as_str(), &chapter.text);
+        }
+        assert!(!index.is_empty());
+        let hits = index.search("\"single man\"").unwrap();
+        assert_eq!(hits[0].name, "Chapter 1");
+        let hits = index
+            .search("pemberley AND housekeeper NOT wickham")
+            .unwrap();
+        assert!(!hits.is_empty());
+        for hit in hits {
+            let text = chapters[hit.doc].text.to_lowercase();
+            assert!(text.contains("housekeeper"));
+            assert!(!text.contains("wickham"));
+        }
+    }
+}
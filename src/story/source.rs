@@ -0,0 +1,123 @@
+use super::{FetchClient, Progress, ProgressSender, StoryError};
+use reqwest::Url;
+use std::io;
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Where read_story gets its text from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorySource {
+    Http(String),
+    File(PathBuf),
+    Stdin,
+    Text(String),
+}
+
+impl StorySource {
+    pub async fn load(
+        &self,
+        client: &FetchClient,
+        progress: &ProgressSender,
+    ) -> Result<String, StoryError> {
+        let bytes = match self {
+            StorySource::Http(url) => client.fetch(url, progress).await?,
+            StorySource::File(path) => {
+                read_all(tokio::fs::File::open(path).await?, progress).await?
+            }
+            StorySource::Stdin => read_all(tokio::io::stdin(), progress).await?,
+            StorySource::Text(text) => return Ok(text.clone()),
+        };
+        progress.send(Progress::Decoding);
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+/// Reads to the end in chunks so local sources report progress like downloads
+async fn read_all<R: AsyncRead + Unpin>(
+    mut reader: R,
+    progress: &ProgressSender,
+) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&buffer[..n]);
+        progress.send(Progress::Downloaded {
+            bytes: bytes.len(),
+            total: None,
+        });
+    }
+}
+
+/// Reads "-" as stdin, http(s):// and file:// as URLs and anything else as a file path
+impl From<&str> for StorySource {
+    fn from(source: &str) -> StorySource {
+        if source == "-" {
+            return StorySource::Stdin;
+        }
+        match Url::parse(source) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                StorySource::Http(source.to_string())
+            }
+            Ok(url) if url.scheme() == "file" => {
+                StorySource::File(url.to_file_path().unwrap_or_else(|_| url.path().into()))
+            }
+            _ => StorySource::File(source.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story::test_server::test_client;
+    use crate::test_support::BOOK;
+
+    #[test]
+    fn from_str_picks_source() {
+        assert_eq!(StorySource::from("-"), StorySource::Stdin);
+        assert_eq!(
+            StorySource::from("https://example.com/book.txt"),
+            StorySource::Http("https://example.com/book.txt".into())
+        );
+        assert_eq!(
+            StorySource::from("http://localhost:8080/book.txt"),
+            StorySource::Http("http://localhost:8080/book.txt".into())
+        );
+        assert_eq!(
+            StorySource::from("file:///tmp/my%20book.txt"),
+            StorySource::File("/tmp/my book.txt".into())
+        );
+        assert_eq!(
+            StorySource::from("data/book.txt"),
+            StorySource::File("data/book.txt".into())
+        );
+    }
+
+    async fn load(source: StorySource) -> Result<String, StoryError> {
+        source
+            .load(&test_client(0), &ProgressSender::default())
+            .await
+    }
+
+    #[tokio::test]
+    async fn loads_text_and_files() {
+        let text = StorySource::Text("Once upon a time".into());
+        assert_eq!(load(text).await.unwrap(), "Once upon a time");
+        let file = load("data/book.txt".into()).await.unwrap();
+        assert_eq!(file, BOOK);
+        let path = std::env::current_dir().unwrap().join("data/book.txt");
+        let url = Url::from_file_path(path).unwrap();
+        let file_url = load(url.as_str().into()).await.unwrap();
+        assert_eq!(file_url, file);
+    }
+
+    #[tokio::test]
+    async fn missing_file_is_a_read_error() {
+        let missing = load("data/missing.txt".into()).await;
+        assert!(matches!(missing, Err(StoryError::Read(_))));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{FetchClient, FetchOptions};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A client with a short timeout and backoff so failing requests end quickly
+pub fn test_client(retries: u32) -> FetchClient {
+    FetchClient::new(FetchOptions {
+        timeout: Duration::from_millis(500),
+        retries,
+        backoff: Duration::from_millis(1),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+/// Serves a canned HTTP response to every connection and returns the base URL
+pub async fn serve(status: &str, body: &[u8]) -> String {
+    let response = response(status, &[], body);
+    serve_with(move |_, _| response.clone()).await
+}
+
+/// Serves whatever the handler returns given the connection number and the request head
+pub async fn serve_with<F>(handler: F) -> String
+where
+    F: Fn(usize, &str) -> Vec<u8> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buffer[..n]),
+                }
+            }
+            let index = connections.fetch_add(1, Ordering::SeqCst);
+            let response = handler(index, &String::from_utf8_lossy(&request));
+            let _ = stream.write_all(&response).await;
+        }
+    });
+    format!("http://{}", address)
+}
+
+/// Builds a response with a Content-Length, extra headers are written as is
+pub fn response(status: &str, headers: &[&str], body: &[u8]) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n", status, body.len());
+    for header in headers {
+        head.push_str(header);
+        head.push_str("\r\n");
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    let mut response = head.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+/// Accepts connections but never answers them
+pub async fn hang() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            open.push(stream);
+        }
+    });
+    format!("http://{}", address)
+}
+
+/// Returns a URL where nothing is listening
+pub async fn closed() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
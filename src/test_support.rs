@@ -0,0 +1,23 @@
+use hashbrown::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Pride and Prejudice as downloaded from Project Gutenberg, header and all
+pub const BOOK: &str = include_str!("../data/book.txt");
+
+/// A frequency table from key and count pairs
+pub fn counts(entries: &[(&str, u32)]) -> HashMap<String, u32> {
+    entries.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+}
+
+/// A path in the temp directory that no other test in this or another run
+/// is using. Nothing is created there.
+pub fn temp_path(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "learn_more_rust_{}_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst),
+        name
+    ))
+}